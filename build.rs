@@ -0,0 +1,108 @@
+// Copyright 2018 Jason Brewer and Gavin Megson
+//
+//! Compiles the CMU phonetic dictionary into the compact index embedded by
+//! the `embedded-cmu` feature.
+//!
+//! The index is written to `OUT_DIR` as four files:
+//!
+//! * `cmu_words.bin` - every headword, sorted bytewise and concatenated
+//! * `cmu_phones.bin` - the pronunciations, one byte per phone, with the
+//!   variants of a headword separated by a 255 byte
+//! * `cmu_index.bin` - little-endian `u32` pairs giving the start of each
+//!   headword and pronunciation, followed by a pair giving the ends
+//! * `cmu_symbols.txt` - the phone for each byte value, one per line
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// Separates the pronunciations of a headword in `cmu_phones.bin`.
+const VARIANT_SEPARATOR: u8 = 255;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    if env::var_os("CARGO_FEATURE_EMBEDDED_CMU").is_none() {
+        return;
+    }
+    println!("cargo:rerun-if-changed=cmu_data/cmudict-0.7b.txt");
+
+    let dict = File::open("cmu_data/cmudict-0.7b.txt").expect("file not found: cmudict-0.7b.txt");
+
+    // A BTreeMap keeps the headwords sorted bytewise, which is the order the
+    // runtime binary search expects. Alternate pronunciations such as
+    // AARONSON(1) are grouped under their headword.
+    let mut entries: BTreeMap<String, Vec<(usize, Vec<String>)>> = BTreeMap::new();
+    for line in BufReader::new(dict).lines() {
+        let line = line.expect("unreadable line in cmudict-0.7b.txt");
+        let mut substrings = line.splitn(2, ' ');
+        let (head, variant) = split_variant(substrings.next().unwrap());
+        let tail: Vec<String> = substrings
+            .next()
+            .unwrap_or("")
+            .split_whitespace()
+            .map(|phone| phone.to_string())
+            .collect();
+        entries.entry(head.to_string()).or_default().push((variant, tail));
+    }
+
+    let mut symbols: Vec<String> = Vec::new();
+    let mut words = Vec::new();
+    let mut phones = Vec::new();
+    let mut index = Vec::new();
+    for (head, tails) in &mut entries {
+        push_u32(&mut index, words.len());
+        push_u32(&mut index, phones.len());
+        words.extend_from_slice(head.as_bytes());
+        tails.sort_by_key(|&(variant, _)| variant);
+        for (i, (_, tail)) in tails.iter().enumerate() {
+            if i > 0 {
+                phones.push(VARIANT_SEPARATOR);
+            }
+            for phone in tail {
+                let code = match symbols.iter().position(|symbol| symbol == phone) {
+                    Some(code) => code,
+                    None => {
+                        symbols.push(phone.clone());
+                        symbols.len() - 1
+                    }
+                };
+                assert!(code < VARIANT_SEPARATOR as usize, "too many distinct CMU phones");
+                phones.push(code as u8);
+            }
+        }
+    }
+    push_u32(&mut index, words.len());
+    push_u32(&mut index, phones.len());
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let out_dir = Path::new(&out_dir);
+    write_file(&out_dir.join("cmu_words.bin"), &words);
+    write_file(&out_dir.join("cmu_phones.bin"), &phones);
+    write_file(&out_dir.join("cmu_index.bin"), &index);
+    write_file(&out_dir.join("cmu_symbols.txt"), symbols.join("\n").as_bytes());
+}
+
+/// Splits a headword such as `AARONSON(1)` into the word and the index of
+/// the pronunciation, the same way as `kana::cmu` does at runtime.
+fn split_variant(head: &str) -> (&str, usize) {
+    if head.ends_with(')') {
+        if let Some(open) = head.rfind('(') {
+            if let Ok(variant) = head[open + 1..head.len() - 1].parse() {
+                return (&head[..open], variant);
+            }
+        }
+    }
+    (head, 0)
+}
+
+fn push_u32(buffer: &mut Vec<u8>, value: usize) {
+    assert!(value <= u32::MAX as usize, "CMU dictionary too large to index");
+    buffer.extend_from_slice(&(value as u32).to_le_bytes());
+}
+
+fn write_file(path: &Path, contents: &[u8]) {
+    let mut file = BufWriter::new(File::create(path).expect("unable to create build output"));
+    file.write_all(contents).expect("unable to write build output");
+}
@@ -1,132 +1,644 @@
+use std::env;
 use std::fs::File;
-use std::io::{BufRead,BufReader};
+use std::io::{BufRead,BufReader,Read};
 use std::collections::HashMap;
+use std::path::{Path,PathBuf};
+#[cfg(feature = "embedded-cmu")]
+use kana::embedded::{EmbeddedIndex, JAP_PHONES};
+use kana::error::KanaError;
+use kana::loan::{default_epenthesis_rules, default_onset_rules, geminate_consonants,
+    insert_vowels, join_onsets, lengthen_vowels, EpenthesisRule, OnsetRule, Phone};
 use kana::trans::*;
 
+/// Environment variable naming the directory that holds the CMU data files.
+pub const CMU_DATA_ENV: &str = "TO_KANA_CMU_DATA";
+/// Directory searched for the CMU data files when `CMU_DATA_ENV` is not set.
+pub const DEFAULT_CMU_DATA_DIR: &str = "cmu_data";
+/// File name of the CMU phonetic dictionary.
+pub const CMU_DICT_FILE: &str = "cmudict-0.7b.txt";
+/// File name of the map from CMU phones to Japanese-ready phones.
+pub const CMU_PHONES_FILE: &str = "cmuphones.txt";
+
+/// Opens a data file, reporting its path if it cannot be found.
+fn open_data(path: &Path) -> Result<BufReader<File>, KanaError> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|_| KanaError::DataFileMissing { path: path.to_path_buf() })
+}
 
 /// Make map from CMU phones into japanese-like phones.
 /// The CMU phones list has been edited to include
 /// the japanese-like phones.
-/// 
+///
 /// Takes a file of whitespace-delineated key/value
 /// pairs separated in lines and creates a HashMap.
-fn make_jap_map() -> HashMap<String, String> {
+///
+/// `path` is only used to report errors.
+fn make_jap_map<R: BufRead>(reader: R, path: &Path) -> Result<HashMap<String, String>, KanaError> {
     let mut phones = HashMap::new();
-    let file = File::open("cmu_data/cmuphones.txt".to_string())
-                    .expect("file not found: cmuphones.txt");
-    for line in BufReader::new(file).lines() {
-        let next = line.expect("out of lines MJM");
+    for (number, line) in reader.lines().enumerate() {
+        let malformed = KanaError::MalformedData { path: path.to_path_buf(), line: number + 1 };
+        let next = line.map_err(|_| malformed.clone())?;
         let substrings: Vec<&str> = next
                             .split_whitespace()
                             .take(2)
                             .collect();
+        if substrings.len() != 2 {
+            return Err(malformed);
+        }
         phones.insert(substrings[0].to_string()
                      ,substrings[1].to_string());
 
     }
-    phones
+    Ok(phones)
 }
 
 /// Make hashmap from CMU phones to Japanese phones.
 /// The Carnegie Mellon University phonetic dictionary
 /// was downloaded, and non-alphabetic keys were deleted.
-fn make_cmu_map() -> HashMap<String, String> {
-    let mut phones = HashMap::new();
-    let file = File::open("cmu_data/cmudict-0.7b.txt".to_string())
-                    .expect("file not found cmudict-0.7b.txt");
-    let mut reader = BufReader::new(file).lines();
-    while let Some(line) = reader.next() {
-        let next = line.expect("out of lines MCM");
+///
+/// Alternate pronunciations such as `AARONSON(1)` are stored with their
+/// headword, in variant order.
+///
+/// `path` is only used to report errors.
+fn make_cmu_map<R: BufRead>(reader: R, path: &Path) -> Result<HashMap<String, Vec<String>>, KanaError> {
+    let mut variants: HashMap<String, Vec<(usize, String)>> = HashMap::new();
+    for (number, line) in reader.lines().enumerate() {
+        let malformed = KanaError::MalformedData { path: path.to_path_buf(), line: number + 1 };
+        let next = line.map_err(|_| malformed.clone())?;
         let substrings: Vec<&str> = next.splitn(2,' ')
                                         .collect();
+        if substrings.len() != 2 {
+            return Err(malformed);
+        }
+
+        let (head, variant) = split_variant(substrings[0]);
+        let tail = substrings[1].trim().to_string();
 
-        let head = substrings[0].to_string();
-        let tail = substrings[1].to_string();
+        variants.entry(head.to_string()).or_default().push((variant, tail));
 
-       
-        phones.insert(head,tail);
+    }
 
+    let mut phones = HashMap::new();
+    for (head, mut tails) in variants {
+        tails.sort_by_key(|&(variant, _)| variant);
+        phones.insert(head, tails.into_iter().map(|(_, tail)| tail).collect());
     }
-    phones
+    Ok(phones)
+}
+
+/// Splits a headword such as `AARONSON(1)` into the word and the index of
+/// the pronunciation. Headwords without a suffix are variant 0.
+fn split_variant(head: &str) -> (&str, usize) {
+    if head.ends_with(')') {
+        if let Some(open) = head.rfind('(') {
+            if let Ok(variant) = head[open + 1..head.len() - 1].parse() {
+                return (&head[..open], variant);
+            }
+        }
+    }
+    (head, 0)
 }
 
 
 /// Take english word, get cmu phones, return japanese-ready phones.
 /// These phones should be easily parsed by katakana/hiragana function.
-fn eng_to_jap(word: &str,
-        cmu_map: & HashMap<String, String>,
-        jap_map: & HashMap<String, String>)
-        -> Vec<String> {
-
-    let mut out = Vec::new();
-    let mut eng_phones: Vec<&str> = 
-                        cmu_map.get(&word.to_uppercase())
-                               .expect("not found in cmu")
-                               .split_whitespace()
-                               .collect()
-                               ;
-    for mut phone in &mut eng_phones {
-        // Remove stress markers. (Numbers)
-        let mut has_stress = false;
-        for character in phone.chars() {
-            if character.is_alphabetic() == false {
-                has_stress = true;
-            }
+fn eng_to_jap(word: &str, dictionary: &CmuDictionary) -> Result<Vec<String>, KanaError> {
+    let pronunciation = dictionary
+        .pronunciation(word)
+        .ok_or_else(|| KanaError::CmuWordMissing { word: word.to_string() })?;
+    phones_to_jap(&pronunciation, dictionary)
+}
+
+/// Take one pronunciation from the cmu dictionary, return japanese-ready phones.
+/// The stress markers are used to spell long vowels and geminates before they
+/// are removed, glides are joined to the consonant before them, and every
+/// consonant but n is given a vowel so the phones form roomaji.
+fn phones_to_jap(pronunciation: &str,
+        dictionary: &CmuDictionary)
+        -> Result<Vec<String>, KanaError> {
+
+    let mut phones = Vec::new();
+    for phone in pronunciation.split_whitespace() {
+        phones.push(Phone::parse(phone, &dictionary.jap)?);
+    }
+    lengthen_vowels(&mut phones);
+    join_onsets(&mut phones, &dictionary.onsets);
+    geminate_consonants(&mut phones);
+    insert_vowels(&mut phones, &dictionary.epenthesis);
+
+    Ok(phones
+        .into_iter()
+        .map(|phone| phone.jap)
+        .filter(|jap| !jap.is_empty())
+        .collect())
+}
+
+/// The CMU phonetic dictionary together with the map from CMU phones to
+/// Japanese-ready phones.
+///
+/// Loading parses both data files, so a dictionary should be loaded once and
+/// reused for every conversion. `CmuDictionary` is `Send + Sync` and can be
+/// shared between threads; `cmu_hiragana` and `cmu_katakana` use an instance
+/// that is loaded the first time either of them is called.
+///
+/// With the `embedded-cmu` feature the dictionary is also compiled into the
+/// binary, see `CmuDictionary::embedded`.
+///
+/// # Examples
+///
+/// ```
+/// use to_kana::kana::cmu::CmuDictionary;
+///
+/// let dictionary = CmuDictionary::load().unwrap();
+/// assert_eq!(dictionary.katakana("rust").unwrap(), "ラスト");
+/// ```
+#[derive(Debug)]
+pub struct CmuDictionary {
+    cmu: Entries,
+    jap: HashMap<String, String>,
+    epenthesis: Vec<EpenthesisRule>,
+    onsets: Vec<OnsetRule>,
+}
+
+/// Where the pronunciations of a `CmuDictionary` are looked up.
+#[derive(Debug)]
+enum Entries {
+    Parsed(HashMap<String, Vec<String>>),
+    #[cfg(feature = "embedded-cmu")]
+    Embedded(EmbeddedIndex),
+}
+
+lazy_static! {
+    static ref SHARED_DICTIONARY: Result<CmuDictionary, KanaError> = load_shared();
+}
+
+impl CmuDictionary {
+    /// Reads the dictionary from the directory named by the
+    /// `TO_KANA_CMU_DATA` environment variable, or from `cmu_data` in the
+    /// current directory if it is not set.
+    ///
+    /// # Errors
+    ///
+    /// Returns `KanaError::DataFileMissing` if the dictionary files cannot be
+    /// opened and `KanaError::MalformedData` if they cannot be parsed.
+    pub fn load() -> Result<CmuDictionary, KanaError> {
+        CmuDictionary::from_dir(default_data_dir())
+    }
+
+    /// Reads `cmudict-0.7b.txt` and `cmuphones.txt` from `dir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `KanaError::DataFileMissing` if the dictionary files cannot be
+    /// opened and `KanaError::MalformedData` if they cannot be parsed.
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<CmuDictionary, KanaError> {
+        let dict_path = dir.as_ref().join(CMU_DICT_FILE);
+        let phones_path = dir.as_ref().join(CMU_PHONES_FILE);
+        Ok(CmuDictionary {
+            cmu: Entries::Parsed(make_cmu_map(open_data(&dict_path)?, &dict_path)?),
+            jap: make_jap_map(open_data(&phones_path)?, &phones_path)?,
+            epenthesis: default_epenthesis_rules(),
+            onsets: default_onset_rules(),
+        })
+    }
+
+    /// Reads the dictionary from any pair of readers, for example files in
+    /// an archive or data already in memory. `dict` must be in the format of
+    /// `cmudict-0.7b.txt` and `phones` in the format of `cmuphones.txt`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `KanaError::MalformedData` if either source cannot be parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use to_kana::kana::cmu::CmuDictionary;
+    ///
+    /// let dict = "CAT  K AE1 T\n";
+    /// let phones = "K\tK\nAE\tA\nT\tT\n";
+    /// let dictionary = CmuDictionary::from_readers(dict.as_bytes(), phones.as_bytes()).unwrap();
+    /// assert_eq!(dictionary.hiragana("cat").unwrap(), "きゃっと");
+    /// ```
+    pub fn from_readers<D: Read, P: Read>(dict: D, phones: P) -> Result<CmuDictionary, KanaError> {
+        Ok(CmuDictionary {
+            cmu: Entries::Parsed(make_cmu_map(BufReader::new(dict), Path::new(CMU_DICT_FILE))?),
+            jap: make_jap_map(BufReader::new(phones), Path::new(CMU_PHONES_FILE))?,
+            epenthesis: default_epenthesis_rules(),
+            onsets: default_onset_rules(),
+        })
+    }
+
+    /// Returns the dictionary compiled into the binary by the `embedded-cmu`
+    /// feature. No files are read and the headwords are searched in place,
+    /// so this is cheap to call.
+    #[cfg(feature = "embedded-cmu")]
+    pub fn embedded() -> CmuDictionary {
+        CmuDictionary {
+            cmu: Entries::Embedded(EmbeddedIndex::new()),
+            jap: make_jap_map(JAP_PHONES.as_bytes(), Path::new(CMU_PHONES_FILE))
+                .expect("embedded cmuphones.txt is well formed"),
+            epenthesis: default_epenthesis_rules(),
+            onsets: default_onset_rules(),
+        }
+    }
+
+    /// Returns the dictionary shared by `cmu_hiragana` and `cmu_katakana`,
+    /// loading it on first use.
+    ///
+    /// With the `embedded-cmu` feature this is the embedded dictionary unless
+    /// the `TO_KANA_CMU_DATA` environment variable names a directory to load.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error from loading the dictionary. A failed load is not
+    /// retried.
+    pub fn shared() -> Result<&'static CmuDictionary, KanaError> {
+        SHARED_DICTIONARY.as_ref().map_err(|error| error.clone())
+    }
+
+    /// Returns the rules that choose the vowel written after a consonant
+    /// with none. See `kana::loan::EpenthesisRule`.
+    pub fn epenthesis_rules(&self) -> &[EpenthesisRule] {
+        &self.epenthesis
+    }
+
+    /// Replaces the epenthesis rules, for example with an extended copy of
+    /// `kana::loan::default_epenthesis_rules`.
+    pub fn set_epenthesis_rules(&mut self, rules: Vec<EpenthesisRule>) {
+        self.epenthesis = rules;
+    }
+
+    /// Returns the rules that join a consonant and a following glide or
+    /// vowel into one onset. See `kana::loan::OnsetRule`.
+    pub fn onset_rules(&self) -> &[OnsetRule] {
+        &self.onsets
+    }
+
+    /// Replaces the onset rules, for example with an extended copy of
+    /// `kana::loan::default_onset_rules`.
+    pub fn set_onset_rules(&mut self, rules: Vec<OnsetRule>) {
+        self.onsets = rules;
+    }
+
+    /// Returns the CMU phones for an English word, separated by spaces.
+    /// Words with several pronunciations return the first one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use to_kana::kana::cmu::CmuDictionary;
+    ///
+    /// let dictionary = CmuDictionary::load().unwrap();
+    /// assert_eq!(dictionary.pronunciation("cat").unwrap(), "K AE1 T");
+    /// ```
+    pub fn pronunciation(&self, word: &str) -> Option<String> {
+        self.pronunciations(word).into_iter().next()
+    }
+
+    /// Returns every pronunciation of an English word in dictionary order,
+    /// so that index 1 is the `WORD(1)` entry. Unknown words return an empty
+    /// vector.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use to_kana::kana::cmu::CmuDictionary;
+    ///
+    /// let dictionary = CmuDictionary::load().unwrap();
+    /// assert_eq!(dictionary.pronunciations("a"), ["AH0", "EY1"]);
+    /// ```
+    pub fn pronunciations(&self, word: &str) -> Vec<String> {
+        let word = word.to_uppercase();
+        match self.cmu {
+            Entries::Parsed(ref cmu) => cmu.get(&word).cloned().unwrap_or_default(),
+            #[cfg(feature = "embedded-cmu")]
+            Entries::Embedded(ref index) => index.get(&word),
+        }
+    }
+
+    /// Transliterates an English word to hiragana.
+    ///
+    /// # Errors
+    ///
+    /// Returns `KanaError::CmuWordMissing` if the word is not in the dictionary,
+    /// `KanaError::UnknownPhone` if a phone has no kana spelling and
+    /// `KanaError::UnknownSyllable` if the spelling cannot be read as kana.
+    pub fn hiragana(&self, word: &str) -> Result<String, KanaError> {
+        let temp: String = eng_to_jap(word, self)?.join("");
+        to_hiragana(temp.as_str(), false)
+    }
+
+    /// Transliterates an English word to katakana.
+    ///
+    /// # Errors
+    ///
+    /// Returns `KanaError::CmuWordMissing` if the word is not in the dictionary,
+    /// `KanaError::UnknownPhone` if a phone has no kana spelling and
+    /// `KanaError::UnknownSyllable` if the spelling cannot be read as kana.
+    pub fn katakana(&self, word: &str) -> Result<String, KanaError> {
+        let temp: String = eng_to_jap(word, self)?.join("");
+        to_katakana(temp.as_str(), false)
+    }
+
+    /// Transliterates every pronunciation of an English word to hiragana.
+    /// The result has one entry per pronunciation, in the order of
+    /// `pronunciations`, even when two of them are spelled the same in kana.
+    ///
+    /// # Errors
+    ///
+    /// Returns `KanaError::CmuWordMissing` if the word is not in the dictionary,
+    /// `KanaError::UnknownPhone` if a phone has no kana spelling and
+    /// `KanaError::UnknownSyllable` if the spelling cannot be read as kana.
+    pub fn hiragana_variants(&self, word: &str) -> Result<Vec<String>, KanaError> {
+        self.variants(word, to_hiragana)
+    }
+
+    /// Transliterates every pronunciation of an English word to katakana.
+    /// The result has one entry per pronunciation, in the order of
+    /// `pronunciations`, even when two of them are spelled the same in kana.
+    ///
+    /// # Errors
+    ///
+    /// Returns `KanaError::CmuWordMissing` if the word is not in the dictionary,
+    /// `KanaError::UnknownPhone` if a phone has no kana spelling and
+    /// `KanaError::UnknownSyllable` if the spelling cannot be read as kana.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use to_kana::kana::cmu::CmuDictionary;
+    ///
+    /// let dictionary = CmuDictionary::load().unwrap();
+    /// assert_eq!(dictionary.katakana_variants("aaronson").unwrap().len(), 2);
+    /// ```
+    pub fn katakana_variants(&self, word: &str) -> Result<Vec<String>, KanaError> {
+        self.variants(word, to_katakana)
+    }
+
+    /// Transliterates one pronunciation of an English word to hiragana.
+    ///
+    /// # Errors
+    ///
+    /// Returns `KanaError::CmuWordMissing` if the word is not in the dictionary
+    /// and `KanaError::CmuVariantMissing` if it has no pronunciation `variant`,
+    /// as well as `KanaError::UnknownPhone` if a phone has no kana spelling and
+    /// `KanaError::UnknownSyllable` if the spelling cannot be read as kana.
+    pub fn hiragana_variant(&self, word: &str, variant: usize) -> Result<String, KanaError> {
+        self.variant(word, variant, to_hiragana)
+    }
+
+    /// Transliterates one pronunciation of an English word to katakana.
+    ///
+    /// # Errors
+    ///
+    /// Returns `KanaError::CmuWordMissing` if the word is not in the dictionary
+    /// and `KanaError::CmuVariantMissing` if it has no pronunciation `variant`,
+    /// as well as `KanaError::UnknownPhone` if a phone has no kana spelling and
+    /// `KanaError::UnknownSyllable` if the spelling cannot be read as kana.
+    pub fn katakana_variant(&self, word: &str, variant: usize) -> Result<String, KanaError> {
+        self.variant(word, variant, to_katakana)
+    }
+
+    fn variants(&self, word: &str, to_kana: fn(&str, bool) -> Result<String, KanaError>)
+            -> Result<Vec<String>, KanaError> {
+        let pronunciations = self.pronunciations(word);
+        if pronunciations.is_empty() {
+            return Err(KanaError::CmuWordMissing { word: word.to_string() });
         }
+        pronunciations
+            .iter()
+            .map(|phones| to_kana(&phones_to_jap(phones, self)?.join(""), false))
+            .collect()
+    }
 
-        let mut phonestring = phone.to_string();
-        if has_stress == true {
-            // Stress marker is always at end.
-            phonestring.pop();
+    fn variant(&self, word: &str, variant: usize, to_kana: fn(&str, bool) -> Result<String, KanaError>)
+            -> Result<String, KanaError> {
+        let pronunciations = self.pronunciations(word);
+        if pronunciations.is_empty() {
+            return Err(KanaError::CmuWordMissing { word: word.to_string() });
+        }
+        match pronunciations.get(variant) {
+            Some(phones) => to_kana(&phones_to_jap(phones, self)?.join(""), false),
+            None => Err(KanaError::CmuVariantMissing { word: word.to_string(), variant }),
         }
-        
-        out.push(jap_map.get(phonestring.as_str())
-                        .expect("not in japanese-ready dictionary")
-                        .to_string());
     }
-    out
+}
+
+/// Returns the directory `CmuDictionary::load` reads from.
+fn default_data_dir() -> PathBuf {
+    env::var_os(CMU_DATA_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_CMU_DATA_DIR))
+}
+
+/// Loads the dictionary behind `CmuDictionary::shared`.
+#[cfg(not(feature = "embedded-cmu"))]
+fn load_shared() -> Result<CmuDictionary, KanaError> {
+    CmuDictionary::load()
+}
+
+/// Loads the dictionary behind `CmuDictionary::shared`.
+#[cfg(feature = "embedded-cmu")]
+fn load_shared() -> Result<CmuDictionary, KanaError> {
+    match env::var_os(CMU_DATA_ENV) {
+        Some(dir) => CmuDictionary::from_dir(dir),
+        None => Ok(CmuDictionary::embedded()),
+    }
 }
 
 /// Full process functions, from english->CMU->japanese
-pub fn cmu_hiragana(word: &str) -> String {
-    let cmu = make_cmu_map();
-    let jap = make_jap_map();
-    let temp: String = eng_to_jap(word,&cmu,&jap).join("");
-    to_hiragana(temp.as_str(), true).expect("to_hiragana from cmu_hiragana failed")
+///
+/// # Errors
+///
+/// Returns `KanaError::CmuWordMissing` if the word is not in the dictionary
+/// and `KanaError::DataFileMissing` if the dictionary files cannot be opened,
+/// as well as `KanaError::UnknownPhone` if a phone has no kana spelling and
+/// `KanaError::UnknownSyllable` if the spelling cannot be read as kana.
+pub fn cmu_hiragana(word: &str) -> Result<String, KanaError> {
+    CmuDictionary::shared()?.hiragana(word)
+}
+
+/// Katakana counterpart of `cmu_hiragana`.
+///
+/// # Errors
+///
+/// Returns `KanaError::CmuWordMissing` if the word is not in the dictionary
+/// and `KanaError::DataFileMissing` if the dictionary files cannot be opened,
+/// as well as `KanaError::UnknownPhone` if a phone has no kana spelling and
+/// `KanaError::UnknownSyllable` if the spelling cannot be read as kana.
+pub fn cmu_katakana(word: &str) -> Result<String, KanaError> {
+    CmuDictionary::shared()?.katakana(word)
+}
+
+/// Returns the hiragana for every pronunciation of an English word, using
+/// the shared dictionary. See `CmuDictionary::hiragana_variants`.
+pub fn cmu_hiragana_variants(word: &str) -> Result<Vec<String>, KanaError> {
+    CmuDictionary::shared()?.hiragana_variants(word)
 }
 
-pub fn cmu_katakana(word: &str) -> String {
-    let cmu = make_cmu_map();
-    let jap = make_jap_map();
-    let temp: String = eng_to_jap(word,&cmu,&jap).join("");
-    to_katakana(temp.as_str(), true).expect("to_katakana from cmu_katakana failed")
+/// Returns the katakana for every pronunciation of an English word, using
+/// the shared dictionary. See `CmuDictionary::katakana_variants`.
+pub fn cmu_katakana_variants(word: &str) -> Result<Vec<String>, KanaError> {
+    CmuDictionary::shared()?.katakana_variants(word)
 }
 
 /// Tests
 
 #[test]
 fn cmu_katakana_test() {
-    assert_eq!("エラン", cmu_katakana("aaron"));
+    assert_eq!("エラン", cmu_katakana("aaron").unwrap());
 }
 
 #[test]
 fn cmu_hiragana_test() {
-    assert_eq!("がべん", cmu_hiragana("Gavin"));
+    assert_eq!("ぎゃべん", cmu_hiragana("Gavin").unwrap());
 }
 
 #[test]
 fn cmu_dict_tests() {
-    let cmumap = make_cmu_map();
-    let japmap = make_jap_map();
-    
+    let dictionary = CmuDictionary::from_dir(DEFAULT_CMU_DATA_DIR).unwrap();
+
     assert_eq!(
-        eng_to_jap("AARON", &cmumap, &japmap),
+        eng_to_jap("AARON", &dictionary).unwrap(),
         vec![
             "E".to_string(),
-            "R".to_string(), 
-            "A".to_string(), 
+            "R".to_string(),
+            "A".to_string(),
             "N".to_string(),
         ],
         "AARON failed."
     );
-}
\ No newline at end of file
+}
+
+#[test]
+fn cmu_missing_word_test() {
+    assert_eq!(
+        Err(KanaError::CmuWordMissing { word: "qwxzzy".to_string() }),
+        cmu_katakana("qwxzzy")
+    );
+}
+
+#[test]
+fn cmu_shared_dictionary_test() {
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    let first = CmuDictionary::shared().unwrap();
+    let second = CmuDictionary::shared().unwrap();
+    assert_send_sync(first);
+    assert!(::std::ptr::eq(first, second));
+}
+
+#[test]
+fn cmu_missing_data_dir_test() {
+    assert_eq!(
+        KanaError::DataFileMissing { path: Path::new("no_such_dir").join(CMU_DICT_FILE) },
+        CmuDictionary::from_dir("no_such_dir").unwrap_err()
+    );
+}
+
+#[test]
+fn cmu_malformed_reader_test() {
+    assert_eq!(
+        KanaError::MalformedData { path: PathBuf::from(CMU_PHONES_FILE), line: 2 },
+        CmuDictionary::from_readers("CAT  K AE1 T\n".as_bytes(), "K\tK\nAE\n".as_bytes()).unwrap_err()
+    );
+}
+
+#[cfg(feature = "embedded-cmu")]
+#[test]
+fn cmu_embedded_dictionary_test() {
+    let embedded = CmuDictionary::embedded();
+    let parsed = CmuDictionary::from_dir(DEFAULT_CMU_DATA_DIR).unwrap();
+    for word in &["a", "aaron", "aaronson", "cat", "rust", "zywicki"] {
+        assert_eq!(parsed.pronunciations(word), embedded.pronunciations(word));
+    }
+    assert!(embedded.pronunciations("qwxzzy").is_empty());
+    assert_eq!("ラスト", embedded.katakana("rust").unwrap());
+}
+
+#[test]
+fn cmu_variants_test() {
+    let dictionary = CmuDictionary::shared().unwrap();
+    assert_eq!(
+        vec!["EH1 R AH0 N S AH0 N".to_string(), "AA1 R AH0 N S AH0 N".to_string()],
+        dictionary.pronunciations("Aaronson")
+    );
+    let variants = dictionary.katakana_variants("aaronson").unwrap();
+    assert_eq!(2, variants.len());
+    assert_eq!(variants[1], dictionary.katakana_variant("aaronson", 1).unwrap());
+    assert_eq!(
+        Err(KanaError::CmuVariantMissing { word: "aaronson".to_string(), variant: 2 }),
+        dictionary.katakana_variant("aaronson", 2)
+    );
+}
+
+#[test]
+fn cmu_stressed_long_vowel_test() {
+    let dictionary = CmuDictionary::shared().unwrap();
+    assert_eq!("チーム", dictionary.katakana("team").unwrap());
+    assert_eq!("ちいむ", dictionary.hiragana("team").unwrap());
+    assert_eq!("バード", dictionary.katakana("bird").unwrap());
+    assert_eq!("カー", dictionary.katakana("car").unwrap());
+    assert_eq!("コード", dictionary.katakana("cord").unwrap());
+    assert_eq!("ハート", dictionary.katakana("heart").unwrap());
+    // AA is short whatever its stress.
+    assert_eq!("ボタル", dictionary.katakana("bottle").unwrap());
+    assert_eq!("コヒ", dictionary.katakana("coffee").unwrap());
+    // EY is long before a consonant but not at the end of a word.
+    assert_eq!("ゲーム", dictionary.katakana("game").unwrap());
+    assert_eq!("ステーキ", dictionary.katakana("steak").unwrap());
+    assert_eq!("ウェイ", dictionary.katakana("way").unwrap());
+}
+
+#[test]
+fn cmu_epenthesis_test() {
+    let dictionary = CmuDictionary::shared().unwrap();
+    assert_eq!("ベスト", dictionary.katakana("best").unwrap());
+    assert_eq!("マッチ", dictionary.katakana("match").unwrap());
+    assert_eq!("バルク", dictionary.katakana("bulk").unwrap());
+    assert_eq!("ケーキ", dictionary.katakana("cake").unwrap());
+    assert_eq!("バス", dictionary.katakana("bath").unwrap());
+    assert_eq!("キング", dictionary.katakana("king").unwrap());
+    assert_eq!("シング", dictionary.katakana("sing").unwrap());
+    assert_eq!("ジャンプ", dictionary.katakana("jump").unwrap());
+    assert_eq!("カンピューター", dictionary.katakana("computer").unwrap());
+}
+
+#[test]
+fn cmu_gemination_test() {
+    let dictionary = CmuDictionary::shared().unwrap();
+    assert_eq!("バッグ", dictionary.katakana("bag").unwrap());
+    assert_eq!("ベッド", dictionary.katakana("bed").unwrap());
+    assert_eq!("タックス", dictionary.katakana("tax").unwrap());
+    assert_eq!("エッジ", dictionary.katakana("edge").unwrap());
+    assert_eq!("ペット", dictionary.katakana("pet").unwrap());
+    assert_eq!("ぺっと", dictionary.hiragana("pet").unwrap());
+    assert_eq!("ホット", dictionary.katakana("hot").unwrap());
+    assert_eq!("ロック", dictionary.katakana("rock").unwrap());
+    assert_eq!("ボックス", dictionary.katakana("box").unwrap());
+    // A voiced consonant after an unstressed vowel and a b are not doubled.
+    assert_eq!("クラブ", dictionary.katakana("club").unwrap());
+}
+
+#[test]
+fn cmu_onset_test() {
+    let dictionary = CmuDictionary::shared().unwrap();
+    assert_eq!("キャット", dictionary.katakana("cat").unwrap());
+    assert_eq!("ギャップ", dictionary.katakana("gap").unwrap());
+    assert_eq!("キュート", dictionary.katakana("cute").unwrap());
+    assert_eq!("アニュアル", dictionary.katakana("annual").unwrap());
+    assert_eq!("フュー", dictionary.katakana("few").unwrap());
+    assert_eq!("ふゅう", dictionary.hiragana("few").unwrap());
+    assert_eq!("クイーン", dictionary.katakana("queen").unwrap());
+    assert_eq!("クイック", dictionary.katakana("quick").unwrap());
+    assert_eq!("ウィン", dictionary.katakana("win").unwrap());
+    assert_eq!("ウォーター", dictionary.katakana("water").unwrap());
+    assert_eq!("ウェブ", dictionary.katakana("web").unwrap());
+    assert_eq!("ツール", dictionary.katakana("tool").unwrap());
+    assert_eq!("ツーン", dictionary.katakana("tune").unwrap());
+    assert_eq!("ツー", dictionary.katakana("two").unwrap());
+    assert_eq!("イェス", dictionary.katakana("yes").unwrap());
+    assert_eq!("いぇす", dictionary.hiragana("yes").unwrap());
+}
@@ -0,0 +1,220 @@
+use kana::options::KanaOptions;
+use kana::script::katakana_to_halfwidth;
+use kana::trans::{
+    continues_syllable, starts_doubled_n, starts_geminate, starts_labial_n, syllable_kana,
+};
+
+/// Converts roomaji to kana one keystroke at a time, as an input method does.
+///
+/// Keys are held in a pending buffer until they can only be read one way,
+/// then committed as kana. After `k` and `y` the buffer is `ky`, and `a`
+/// commits きゃ. A lone `n` stays pending until the next key shows whether it
+/// starts a syllable or is ん. The keys are read as `to_hiragana_with` reads
+/// them, so `nn` waits for the next key too: before a vowel or y it is ん
+/// and the start of the next syllable, as in konnichiwa. In wāpuro input,
+/// as in an input method, `nn` is always ん.
+///
+/// # Example
+///
+/// ```
+/// use to_kana::Composer;
+///
+/// let mut composer = Composer::new();
+/// assert_eq!(composer.push('k'), "");
+/// assert_eq!(composer.push('y'), "");
+/// assert_eq!(composer.pending(), "ky");
+/// assert_eq!(composer.push('a'), "きゃ");
+/// assert_eq!(composer.push('n'), "");
+/// assert_eq!(composer.flush(), "ん");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Composer {
+    options: KanaOptions,
+    katakana: bool,
+    buffer: String,
+    // The vowel of the last committed syllable, which a repeated vowel
+    // lengthens with the choonpu in katakana
+    last_vowel: Option<char>,
+}
+
+impl Composer {
+    /// Returns a composer that writes hiragana and reads modern Hepburn.
+    pub fn new() -> Composer {
+        Composer::default()
+    }
+
+    /// Returns a composer that writes katakana if `katakana` is set, or
+    /// hiragana otherwise, reading the input as set out by `options`.
+    pub fn with_options(katakana: bool, options: KanaOptions) -> Composer {
+        Composer {
+            options,
+            katakana,
+            ..Composer::default()
+        }
+    }
+
+    /// Adds a keystroke and returns the kana it commits, which is empty while
+    /// the keys so far are still pending.
+    pub fn push(&mut self, key: char) -> String {
+        self.buffer.push(key);
+        self.commit(false)
+    }
+
+    /// Removes the last pending key and returns it, or None if nothing is
+    /// pending. Committed kana cannot be taken back.
+    pub fn backspace(&mut self) -> Option<char> {
+        self.buffer.pop()
+    }
+
+    /// Commits whatever is pending and returns it, with final n as ん and
+    /// keys that do not make a syllable passed through as they were typed.
+    pub fn flush(&mut self) -> String {
+        let output = self.commit(true);
+        self.last_vowel = None;
+        output
+    }
+
+    /// Returns the keys that have not been committed yet.
+    pub fn pending(&self) -> &str {
+        &self.buffer
+    }
+
+    /// Commits as much of the buffer as can be read, or all of it when
+    /// `flush` is set.
+    fn commit(&mut self, flush: bool) -> String {
+        let mut output = String::new();
+        while !self.buffer.is_empty() {
+            let keys = self.buffer.to_lowercase();
+            // Wait for more keys while they could still make a longer syllable,
+            // the tch of a geminate, or final n before a syllable.
+            let waits = continues_syllable(&keys, &self.options)
+                || keys == "tc"
+                || (keys == "nn" && !self.options.wapuro);
+            if !flush && waits {
+                break;
+            }
+            let chars: Vec<char> = keys.chars().collect();
+            let (kana, length) = if starts_doubled_n(&chars, &self.options) {
+                (self.kana("n").unwrap().to_string(), 1)
+            } else if let Some(kana) = self.kana(&keys) {
+                (kana.to_string(), chars.len())
+            } else if starts_geminate(&chars, &self.options) {
+                (self.kana("G").unwrap().to_string(), 1)
+            } else if starts_labial_n(&chars, &self.options) {
+                (self.kana("n").unwrap().to_string(), 1)
+            } else {
+                // The longest syllable at the start of the buffer, or the
+                // first key as it was typed if there is none. A key passed
+                // through ends the vowel that a choonpu could lengthen.
+                let longest = (1..chars.len())
+                    .rev()
+                    .filter_map(|length| {
+                        let syllable: String = chars[..length].iter().collect();
+                        self.kana(&syllable).map(|kana| (kana.to_string(), length))
+                    })
+                    .next();
+                match longest {
+                    Some(found) => found,
+                    None => {
+                        self.last_vowel = None;
+                        (self.buffer.chars().next().unwrap().to_string(), 1)
+                    }
+                }
+            };
+            output.push_str(&kana);
+            self.buffer = self.buffer.chars().skip(length).collect();
+        }
+        if self.katakana && self.options.halfwidth {
+            output = katakana_to_halfwidth(&output);
+        }
+        output
+    }
+
+    /// Returns the kana for a syllable, writing a vowel that repeats the
+    /// vowel before it as the choonpu in katakana. Consonants on their own are
+    /// left as they were typed, as they are only kana in English input.
+    fn kana(&mut self, syllable: &str) -> Option<&'static str> {
+        let consonants = syllable.chars().all(|c| c.is_ascii_lowercase() && !"aiueo".contains(c));
+        if consonants && !["n", "nn", "xn"].contains(&syllable) {
+            return None;
+        }
+        let vowel = syllable.chars().last().filter(|c| "aiueo".contains(*c));
+        let kana = if self.katakana && syllable.len() == 1 && vowel.is_some() && vowel == self.last_vowel {
+            syllable_kana("L", &self.options, true)
+        } else {
+            syllable_kana(syllable, &self.options, self.katakana)
+        };
+        if kana.is_some() {
+            self.last_vowel = vowel;
+        }
+        kana
+    }
+}
+
+#[test]
+fn test_composer_commits_digraph() {
+    let mut composer = Composer::new();
+    assert_eq!("", composer.push('k'));
+    assert_eq!("", composer.push('y'));
+    assert_eq!("ky", composer.pending());
+    assert_eq!("きゃ", composer.push('a'));
+    assert_eq!("", composer.pending());
+}
+
+#[test]
+fn test_composer_final_n() {
+    let mut composer = Composer::new();
+    let typed: String = "hon'yakonnichiwa".chars().map(|key| composer.push(key)).collect();
+    assert_eq!("ほんやこんにちわ", typed);
+    assert_eq!("", composer.push('n'));
+    assert_eq!("n", composer.pending());
+    assert_eq!("ん", composer.push('k'));
+    assert_eq!("か", composer.push('a'));
+    composer.push('n');
+    assert_eq!("ん", composer.flush());
+}
+
+#[test]
+fn test_composer_geminates_and_backspace() {
+    let mut composer = Composer::with_options(true, KanaOptions::default());
+    assert_eq!("", composer.push('k'));
+    assert_eq!("ッ", composer.push('k'));
+    assert_eq!("k", composer.pending());
+    assert_eq!(Some('k'), composer.backspace());
+    assert_eq!(None, composer.backspace());
+    let typed: String = "matchaaa".chars().map(|key| composer.push(key)).collect();
+    assert_eq!("マッチャーー", typed);
+    let mut composer = Composer::with_options(true, KanaOptions { halfwidth: true, ..KanaOptions::default() });
+    assert_eq!("ｶﾞ", composer.push('g').to_string() + &composer.push('a'));
+}
+
+#[test]
+fn test_composer_flush_passes_through_leftovers() {
+    let mut composer = Composer::new();
+    assert_eq!("", composer.push('t'));
+    assert_eq!("", composer.push('s'));
+    assert_eq!("ts", composer.flush());
+    assert_eq!("!", composer.push('!'));
+}
+
+#[test]
+fn test_composer_matches_batch_conversion() {
+    use kana::trans::{to_hiragana_with, to_katakana_with};
+
+    let wapuro = KanaOptions { wapuro: true, ..KanaOptions::default() };
+    let inputs = ["onna", "konnichiwa", "konnnichiha", "kinnyuu", "hon'ya", "shimbun", "matcha", "gakkou", "raamen",
+        "a a", "ka a", "a-a", "aa", "zenn'in"];
+    for options in &[KanaOptions::default(), wapuro] {
+        for input in &inputs {
+            let mut composer = Composer::with_options(false, options.clone());
+            let mut typed: String = input.chars().map(|key| composer.push(key)).collect();
+            typed.push_str(&composer.flush());
+            assert_eq!(to_hiragana_with(input, false, options).unwrap(), typed, "{}", input);
+
+            let mut composer = Composer::with_options(true, options.clone());
+            let mut typed: String = input.chars().map(|key| composer.push(key)).collect();
+            typed.push_str(&composer.flush());
+            assert_eq!(to_katakana_with(input, false, options).unwrap(), typed, "{}", input);
+        }
+    }
+}
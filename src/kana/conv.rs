@@ -4,6 +4,142 @@ use HIRAGANA_KEYS;
 use KATAKANA_KEYS;
 
 
+/// The wa-row kana that are only used in historical spellings, as roomaji,
+/// hiragana and katakana. They are read in place of ウィ and ウェ when
+/// `KanaOptions::historical` is set.
+pub const HISTORICAL_KANA: [(&str, &str, &str); 2] = [
+    ("wi", "\u{3090}", "\u{30F0}"),
+    ("we", "\u{3091}", "\u{30F1}"),
+];
+
+/// Syllables that Kunrei-shiki and Nihon-shiki input read as native kana, as
+/// roomaji, hiragana and katakana. In Hepburn input they are extended kana.
+pub const KUNREI_KANA: [(&str, &str, &str); 3] = [
+    ("ti", "\u{3061}", "\u{30C1}"),
+    ("tu", "\u{3064}", "\u{30C4}"),
+    ("tyu", "\u{3061}\u{3085}", "\u{30C1}\u{30E5}"),
+];
+
+/// Syllables that only Nihon-shiki input reads as native kana, in the same
+/// layout as `KUNREI_KANA`.
+pub const NIHON_SHIKI_KANA: [(&str, &str, &str); 7] = [
+    ("di", "\u{3062}", "\u{30C2}"),
+    ("du", "\u{3065}", "\u{30C5}"),
+    ("dyu", "\u{3062}\u{3085}", "\u{30C2}\u{30E5}"),
+    ("kwa", "\u{304F}\u{308E}", "\u{30AF}\u{30EE}"),
+    ("gwa", "\u{3050}\u{308E}", "\u{30B0}\u{30EE}"),
+    ("wi", "\u{3090}", "\u{30F0}"),
+    ("we", "\u{3091}", "\u{30F1}"),
+];
+
+/// Small kana that are also written on their own, such as the ヶ of 一ヶ月,
+/// in the same layout as `KUNREI_KANA`. Wāpuro input types them with x or l.
+pub const SMALL_KANA: [(&str, &str, &str); 3] = [
+    ("wa", "\u{308E}", "\u{30EE}"),
+    ("ka", "\u{3095}", "\u{30F5}"),
+    ("ke", "\u{3096}", "\u{30F6}"),
+];
+
+/// The key sequences of the common input method roomaji layouts that are
+/// not already in the syllable tables, as roomaji, hiragana and katakana.
+/// They are read when `KanaOptions::wapuro` is set.
+pub const WAPURO_KANA: &[(&str, &str, &str)] = &[
+    // Small kana, typed with x or l
+    ("xa", "\u{3041}", "\u{30A1}"),
+    ("la", "\u{3041}", "\u{30A1}"),
+    ("xi", "\u{3043}", "\u{30A3}"),
+    ("li", "\u{3043}", "\u{30A3}"),
+    ("xu", "\u{3045}", "\u{30A5}"),
+    ("lu", "\u{3045}", "\u{30A5}"),
+    ("xe", "\u{3047}", "\u{30A7}"),
+    ("le", "\u{3047}", "\u{30A7}"),
+    ("xo", "\u{3049}", "\u{30A9}"),
+    ("lo", "\u{3049}", "\u{30A9}"),
+    ("xya", "\u{3083}", "\u{30E3}"),
+    ("lya", "\u{3083}", "\u{30E3}"),
+    ("xyu", "\u{3085}", "\u{30E5}"),
+    ("lyu", "\u{3085}", "\u{30E5}"),
+    ("xyo", "\u{3087}", "\u{30E7}"),
+    ("lyo", "\u{3087}", "\u{30E7}"),
+    ("xtu", "\u{3063}", "\u{30C3}"),
+    ("ltu", "\u{3063}", "\u{30C3}"),
+    ("xtsu", "\u{3063}", "\u{30C3}"),
+    ("ltsu", "\u{3063}", "\u{30C3}"),
+    ("xwa", "\u{308E}", "\u{30EE}"),
+    ("lwa", "\u{308E}", "\u{30EE}"),
+    ("xka", "\u{3095}", "\u{30F5}"),
+    ("lka", "\u{3095}", "\u{30F5}"),
+    ("xke", "\u{3096}", "\u{30F6}"),
+    ("lke", "\u{3096}", "\u{30F6}"),
+    ("xyi", "\u{3043}", "\u{30A3}"),
+    ("lyi", "\u{3043}", "\u{30A3}"),
+    ("xye", "\u{3047}", "\u{30A7}"),
+    ("lye", "\u{3047}", "\u{30A7}"),
+    ("xn", "\u{3093}", "\u{30F3}"),
+    // The vowels typed with y and wh
+    ("yi", "\u{3044}", "\u{30A4}"),
+    ("whu", "\u{3046}", "\u{30A6}"),
+    // The choonpu, which input methods also write in hiragana
+    ("-", "\u{30FC}", "\u{30FC}"),
+    // The historical wa-row
+    ("wyi", "\u{3090}", "\u{30F0}"),
+    ("wye", "\u{3091}", "\u{30F1}"),
+    ("wu", "\u{3046}", "\u{30A6}"),
+    // Kunrei-shiki and Nihon-shiki spellings, which input methods read natively
+    ("ti", "\u{3061}", "\u{30C1}"),
+    ("tu", "\u{3064}", "\u{30C4}"),
+    ("tyu", "\u{3061}\u{3085}", "\u{30C1}\u{30E5}"),
+    ("di", "\u{3062}", "\u{30C2}"),
+    ("du", "\u{3065}", "\u{30C5}"),
+    ("dyu", "\u{3062}\u{3085}", "\u{30C2}\u{30E5}"),
+    // Extended kana typed with h
+    ("tha", "\u{3066}\u{3083}", "\u{30C6}\u{30E3}"),
+    ("the", "\u{3066}\u{3047}", "\u{30C6}\u{30A7}"),
+    ("tho", "\u{3066}\u{3087}", "\u{30C6}\u{30E7}"),
+    ("dha", "\u{3067}\u{3083}", "\u{30C7}\u{30E3}"),
+    ("dhe", "\u{3067}\u{3047}", "\u{30C7}\u{30A7}"),
+    ("dho", "\u{3067}\u{3087}", "\u{30C7}\u{30E7}"),
+    ("wha", "\u{3046}\u{3041}", "\u{30A6}\u{30A1}"),
+    // Digraphs with a small e
+    ("kye", "\u{304D}\u{3047}", "\u{30AD}\u{30A7}"),
+    ("gye", "\u{304E}\u{3047}", "\u{30AE}\u{30A7}"),
+    ("jye", "\u{3058}\u{3047}", "\u{30B8}\u{30A7}"),
+    ("cye", "\u{3061}\u{3047}", "\u{30C1}\u{30A7}"),
+    ("dye", "\u{3062}\u{3047}", "\u{30C2}\u{30A7}"),
+    ("nye", "\u{306B}\u{3047}", "\u{30CB}\u{30A7}"),
+    ("hye", "\u{3072}\u{3047}", "\u{30D2}\u{30A7}"),
+    ("bye", "\u{3073}\u{3047}", "\u{30D3}\u{30A7}"),
+    ("pye", "\u{3074}\u{3047}", "\u{30D4}\u{30A7}"),
+    ("mye", "\u{307F}\u{3047}", "\u{30DF}\u{30A7}"),
+    ("rye", "\u{308A}\u{3047}", "\u{30EA}\u{30A7}"),
+    // The c and q rows
+    ("ca", "\u{304B}", "\u{30AB}"),
+    ("ci", "\u{3057}", "\u{30B7}"),
+    ("cu", "\u{304F}", "\u{30AF}"),
+    ("ce", "\u{305B}", "\u{30BB}"),
+    ("co", "\u{3053}", "\u{30B3}"),
+    ("cya", "\u{3061}\u{3083}", "\u{30C1}\u{30E3}"),
+    ("cyu", "\u{3061}\u{3085}", "\u{30C1}\u{30E5}"),
+    ("cyo", "\u{3061}\u{3087}", "\u{30C1}\u{30E7}"),
+    ("qa", "\u{304F}\u{3041}", "\u{30AF}\u{30A1}"),
+    ("qi", "\u{304F}\u{3043}", "\u{30AF}\u{30A3}"),
+    ("qu", "\u{304F}", "\u{30AF}"),
+    ("qe", "\u{304F}\u{3047}", "\u{30AF}\u{30A7}"),
+    ("qo", "\u{304F}\u{3049}", "\u{30AF}\u{30A9}"),
+    // Digraphs spelled with jy
+    ("jya", "\u{3058}\u{3083}", "\u{30B8}\u{30E3}"),
+    ("jyu", "\u{3058}\u{3085}", "\u{30B8}\u{30E5}"),
+    ("jyo", "\u{3058}\u{3087}", "\u{30B8}\u{30E7}"),
+];
+
+/// Extended syllables for foreign sounds, in the order of their kana in
+/// `initialize_hiragana_keys` and `initialize_katakana_keys`.
+const EXTENDED_SYLLABLES: [&str; 28] = [
+    "she", "che", "tsa", "tsi", "tse", "tso", "ti", "tu", "tyu", "di", "du", "dyu",
+    "fa", "fi", "fe", "fo", "fyu", "va", "vi", "vu", "ve", "vo", "vyu",
+    "kwa", "kwi", "kwe", "kwo", "gwa",
+];
+
 /// Returns a vector of strings that represent Japanese syllables
 pub fn initialize_japanese_syllables() -> Vec<String> {
     // Japanese vowels
@@ -16,12 +152,12 @@ pub fn initialize_japanese_syllables() -> Vec<String> {
     // Japanese digraphs
     let digraphs_vowels = ["a", "u", "o"];
     let digraph_consonants = [
-        "ky", "sh", "ch", "nq", "hy", "my", "ry", "gy", "j", "by", "py",
+        "ky", "sh", "ch", "ny", "hy", "my", "ry", "gy", "j", "by", "py", "dj",
     ];
 
     let mut syllabary = Vec::new();
     for v in &vowels {
-        syllabary.push(format!("{}", v));
+        syllabary.push(v.to_string());
     }
     for c in &consonants {
         for v in &vowels {
@@ -39,15 +175,28 @@ pub fn initialize_japanese_syllables() -> Vec<String> {
     syllabary.push("G".to_string());
     // This is a digraph used in mostly foreign words
     syllabary.push("je".to_string());
+    // These are used for glides in foreign words, as in イェス and ウィンドウ.
+    // They come after the wa-row so that wi and we are read as ウィ and ウェ
+    // rather than the historical ゐ and ゑ, and wo stays を, so ウォ is who.
+    for foreign in &["ye", "wi", "we", "who"] {
+        syllabary.push(foreign.to_string());
+    }
+    // The extended katakana for foreign sounds from the official loanword
+    // spelling guidelines, as in ファン, ティー, ヴァイオリン and チェス.
+    for foreign in &EXTENDED_SYLLABLES {
+        syllabary.push(foreign.to_string());
+    }
 
     // There are a number of syllables that are transcribed differently by
     // convention. This loop is designed to find their phonemic representations
     // and replace them. The iter is sourced from stack overflow,
     // https://stackoverflow.com/questions/30558246/how-do-i-find-the-index-of-an-element-in-an-array-vector-or-slice
     // posted by Mathieu David.
-    let to_replace = ["si", "ti", "tu", "hu", "zi"];
-    let replace_with = ["shi", "chi", "tsu", "fu", "ji"];
-    for i in 0..5 {
+    // The d-row ぢ and づ are spelled dji and dzu so that di and du are left
+    // for ディ and ドゥ.
+    let to_replace = ["si", "ti", "tu", "hu", "zi", "di", "du"];
+    let replace_with = ["shi", "chi", "tsu", "fu", "ji", "dji", "dzu"];
+    for i in 0..to_replace.len() {
         let syllable = to_replace[i];
         let index = syllabary
             .iter()
@@ -149,8 +298,8 @@ pub fn initialize_hiragana_keys() -> Vec<&'static str> {
         "\u{308F}",
         "\u{3090}",
         "NOT USED",
-        "\u{3090}",
         "\u{3091}",
+        "\u{3092}",
         // voiceless velar stop digraph
         "\u{304D}\u{3083}",
         "\u{304D}\u{3085}",
@@ -195,12 +344,50 @@ pub fn initialize_hiragana_keys() -> Vec<&'static str> {
         "\u{3074}\u{3083}",
         "\u{3074}\u{3085}",
         "\u{3074}\u{3087}",
+        // voiced alveolar stop digraph
+        "\u{3062}\u{3083}",
+        "\u{3062}\u{3085}",
+        "\u{3062}\u{3087}",
         // final nasal
         "\u{3093}",
         // small tsu for geminates
         "\u{3063}",
         // foreign digraph
         "\u{3058}\u{3047}",
+        // foreign glides
+        "\u{3044}\u{3047}",
+        "\u{3046}\u{3043}",
+        "\u{3046}\u{3047}",
+        "\u{3046}\u{3049}",
+        // extended kana for foreign sounds
+        "\u{3057}\u{3047}",
+        "\u{3061}\u{3047}",
+        "\u{3064}\u{3041}",
+        "\u{3064}\u{3043}",
+        "\u{3064}\u{3047}",
+        "\u{3064}\u{3049}",
+        "\u{3066}\u{3043}",
+        "\u{3068}\u{3045}",
+        "\u{3066}\u{3085}",
+        "\u{3067}\u{3043}",
+        "\u{3069}\u{3045}",
+        "\u{3067}\u{3085}",
+        "\u{3075}\u{3041}",
+        "\u{3075}\u{3043}",
+        "\u{3075}\u{3047}",
+        "\u{3075}\u{3049}",
+        "\u{3075}\u{3085}",
+        "\u{3094}\u{3041}",
+        "\u{3094}\u{3043}",
+        "\u{3094}",
+        "\u{3094}\u{3047}",
+        "\u{3094}\u{3049}",
+        "\u{3094}\u{3085}",
+        "\u{304F}\u{3041}",
+        "\u{304F}\u{3043}",
+        "\u{304F}\u{3047}",
+        "\u{304F}\u{3049}",
+        "\u{3050}\u{3041}",
     ];
 
     unicodekeys
@@ -298,8 +485,8 @@ pub fn initialize_katakana_keys() -> Vec<&'static str> {
         "\u{30EF}",
         "\u{30F0}",
         "NOT USED",
-        "\u{30F0}",
         "\u{30F1}",
+        "\u{30F2}",
         // voiceless velar stop digraph
         "\u{30AD}\u{30E3}",
         "\u{30AD}\u{30E5}",
@@ -344,12 +531,50 @@ pub fn initialize_katakana_keys() -> Vec<&'static str> {
         "\u{30D4}\u{30E3}",
         "\u{30D4}\u{30E5}",
         "\u{30D4}\u{30E7}",
+        // voiced alveolar stop digraph
+        "\u{30C2}\u{30E3}",
+        "\u{30C2}\u{30E5}",
+        "\u{30C2}\u{30E7}",
         // final nasal
         "\u{30F3}",
         // small tsu for geminates
         "\u{30C3}",
         // foreign digraph
         "\u{30B8}\u{30A7}",
+        // foreign glides
+        "\u{30A4}\u{30A7}",
+        "\u{30A6}\u{30A3}",
+        "\u{30A6}\u{30A7}",
+        "\u{30A6}\u{30A9}",
+        // extended kana for foreign sounds
+        "\u{30B7}\u{30A7}",
+        "\u{30C1}\u{30A7}",
+        "\u{30C4}\u{30A1}",
+        "\u{30C4}\u{30A3}",
+        "\u{30C4}\u{30A7}",
+        "\u{30C4}\u{30A9}",
+        "\u{30C6}\u{30A3}",
+        "\u{30C8}\u{30A5}",
+        "\u{30C6}\u{30E5}",
+        "\u{30C7}\u{30A3}",
+        "\u{30C9}\u{30A5}",
+        "\u{30C7}\u{30E5}",
+        "\u{30D5}\u{30A1}",
+        "\u{30D5}\u{30A3}",
+        "\u{30D5}\u{30A7}",
+        "\u{30D5}\u{30A9}",
+        "\u{30D5}\u{30E5}",
+        "\u{30F4}\u{30A1}",
+        "\u{30F4}\u{30A3}",
+        "\u{30F4}",
+        "\u{30F4}\u{30A7}",
+        "\u{30F4}\u{30A9}",
+        "\u{30F4}\u{30E5}",
+        "\u{30AF}\u{30A1}",
+        "\u{30AF}\u{30A3}",
+        "\u{30AF}\u{30A7}",
+        "\u{30AF}\u{30A9}",
+        "\u{30B0}\u{30A1}",
         // choonpu for long vowels
         "\u{30FC}",
     ];
@@ -374,9 +599,7 @@ pub fn initialize_hiragana() -> HashMap<String, String> {
     }
 
     // These syllables are phonologically possible but do not have associated kana.
-    hiragana_table.remove("yi");
-    hiragana_table.remove("ye");
-    hiragana_table.remove("wu");
+    hiragana_table.retain(|_, kana| kana != "NOT USED");
 
     // Additional mappings to correspond with English consonants
     hiragana_table.insert("b".to_string(), "\u{3076}".to_string());
@@ -394,12 +617,51 @@ pub fn initialize_hiragana() -> HashMap<String, String> {
     hiragana_table.insert("sh".to_string(), "\u{3057}".to_string());
     hiragana_table.insert("t".to_string(), "\u{3068}".to_string());
     hiragana_table.insert("si".to_string(), "\u{3057}".to_string());
-    hiragana_table.insert("ti".to_string(), "\u{3061}".to_string());
-    hiragana_table.insert("tu".to_string(), "\u{3064}".to_string());
     hiragana_table.insert("hu".to_string(), "\u{3075}".to_string());
     hiragana_table.insert("zi".to_string(), "\u{3058}".to_string());
     hiragana_table.insert("m".to_string(), "\u{3080}".to_string());
 
+    // Kunrei-shiki and Nihon-shiki digraphs that no Hepburn syllable uses
+    hiragana_table.insert("sya".to_string(), "\u{3057}\u{3083}".to_string());
+    hiragana_table.insert("syu".to_string(), "\u{3057}\u{3085}".to_string());
+    hiragana_table.insert("syo".to_string(), "\u{3057}\u{3087}".to_string());
+    hiragana_table.insert("zya".to_string(), "\u{3058}\u{3083}".to_string());
+    hiragana_table.insert("zyu".to_string(), "\u{3058}\u{3085}".to_string());
+    hiragana_table.insert("zyo".to_string(), "\u{3058}\u{3087}".to_string());
+    hiragana_table.insert("dya".to_string(), "\u{3062}\u{3083}".to_string());
+    hiragana_table.insert("dyo".to_string(), "\u{3062}\u{3087}".to_string());
+    hiragana_table.insert("tya".to_string(), "\u{3061}\u{3083}".to_string());
+    hiragana_table.insert("tyo".to_string(), "\u{3061}\u{3087}".to_string());
+    hiragana_table.insert("sye".to_string(), "\u{3057}\u{3047}".to_string());
+    hiragana_table.insert("zye".to_string(), "\u{3058}\u{3047}".to_string());
+    hiragana_table.insert("tye".to_string(), "\u{3061}\u{3047}".to_string());
+
+    // Final n written to keep it apart from a following vowel or y, as in
+    // hon'ya and kin'en, or doubled as in an input method, or both
+    hiragana_table.insert("n'".to_string(), "\u{3093}".to_string());
+    hiragana_table.insert("nn".to_string(), "\u{3093}".to_string());
+    hiragana_table.insert("nn'".to_string(), "\u{3093}".to_string());
+    // The nq digraphs that were used for ny before n' was supported
+    hiragana_table.insert("nqa".to_string(), "\u{306B}\u{3083}".to_string());
+    hiragana_table.insert("nqu".to_string(), "\u{306B}\u{3085}".to_string());
+    hiragana_table.insert("nqo".to_string(), "\u{306B}\u{3087}".to_string());
+
+    // Input method spellings of the extended kana. Kunrei-shiki and
+    // Nihon-shiki romanization write ティ, トゥ and their kin this way, as
+    // ti and tu are チ and ツ there, and Nihon-shiki writes ウィ, ウェ and
+    // クァ as whi, whe and qwa, as wi, we and kwa are ゐ, ゑ and くゎ. No input
+    // method spelling sets グァ apart from ぐゎ, so it is gwha, after wha.
+    hiragana_table.insert("whi".to_string(), "\u{3046}\u{3043}".to_string());
+    hiragana_table.insert("whe".to_string(), "\u{3046}\u{3047}".to_string());
+    hiragana_table.insert("twu".to_string(), "\u{3068}\u{3045}".to_string());
+    hiragana_table.insert("dwu".to_string(), "\u{3069}\u{3045}".to_string());
+    hiragana_table.insert("thi".to_string(), "\u{3066}\u{3043}".to_string());
+    hiragana_table.insert("thu".to_string(), "\u{3066}\u{3085}".to_string());
+    hiragana_table.insert("dhi".to_string(), "\u{3067}\u{3043}".to_string());
+    hiragana_table.insert("dhu".to_string(), "\u{3067}\u{3085}".to_string());
+    hiragana_table.insert("qwa".to_string(), "\u{304F}\u{3041}".to_string());
+    hiragana_table.insert("gwha".to_string(), "\u{3050}\u{3041}".to_string());
+
     
 
     hiragana_table
@@ -426,9 +688,7 @@ pub fn initialize_katakana() -> HashMap<String, String> {
     }
 
     // These syllables are phonologically possible but do not have associated kana.
-    katakana_table.remove("yi");
-    katakana_table.remove("ye");
-    katakana_table.remove("wu");
+    katakana_table.retain(|_, kana| kana != "NOT USED");
 
      // Additional mappings that correspond to English consonants
     katakana_table.insert("b".to_string(), "\u{30D6}".to_string());
@@ -446,18 +706,57 @@ pub fn initialize_katakana() -> HashMap<String, String> {
     katakana_table.insert("sh".to_string(), "\u{30B7}".to_string());
     katakana_table.insert("t".to_string(), "\u{30C8}".to_string());
     katakana_table.insert("si".to_string(), "\u{30B7}".to_string());
-    katakana_table.insert("ti".to_string(), "\u{30C1}".to_string());
-    katakana_table.insert("tu".to_string(), "\u{30C4}".to_string());
     katakana_table.insert("hu".to_string(), "\u{30D5}".to_string());
     katakana_table.insert("zi".to_string(), "\u{30B8}".to_string());
     katakana_table.insert("m".to_string(), "\u{30E0}".to_string());
 
+    // Kunrei-shiki and Nihon-shiki digraphs that no Hepburn syllable uses
+    katakana_table.insert("sya".to_string(), "\u{30B7}\u{30E3}".to_string());
+    katakana_table.insert("syu".to_string(), "\u{30B7}\u{30E5}".to_string());
+    katakana_table.insert("syo".to_string(), "\u{30B7}\u{30E7}".to_string());
+    katakana_table.insert("zya".to_string(), "\u{30B8}\u{30E3}".to_string());
+    katakana_table.insert("zyu".to_string(), "\u{30B8}\u{30E5}".to_string());
+    katakana_table.insert("zyo".to_string(), "\u{30B8}\u{30E7}".to_string());
+    katakana_table.insert("dya".to_string(), "\u{30C2}\u{30E3}".to_string());
+    katakana_table.insert("dyo".to_string(), "\u{30C2}\u{30E7}".to_string());
+    katakana_table.insert("tya".to_string(), "\u{30C1}\u{30E3}".to_string());
+    katakana_table.insert("tyo".to_string(), "\u{30C1}\u{30E7}".to_string());
+    katakana_table.insert("sye".to_string(), "\u{30B7}\u{30A7}".to_string());
+    katakana_table.insert("zye".to_string(), "\u{30B8}\u{30A7}".to_string());
+    katakana_table.insert("tye".to_string(), "\u{30C1}\u{30A7}".to_string());
+
+    // Final n written to keep it apart from a following vowel or y, as in
+    // hon'ya and kin'en, or doubled as in an input method, or both
+    katakana_table.insert("n'".to_string(), "\u{30F3}".to_string());
+    katakana_table.insert("nn".to_string(), "\u{30F3}".to_string());
+    katakana_table.insert("nn'".to_string(), "\u{30F3}".to_string());
+    // The nq digraphs that were used for ny before n' was supported
+    katakana_table.insert("nqa".to_string(), "\u{30CB}\u{30E3}".to_string());
+    katakana_table.insert("nqu".to_string(), "\u{30CB}\u{30E5}".to_string());
+    katakana_table.insert("nqo".to_string(), "\u{30CB}\u{30E7}".to_string());
+
+    // Input method spellings of the extended kana. Kunrei-shiki and
+    // Nihon-shiki romanization write ティ, トゥ and their kin this way, as
+    // ti and tu are チ and ツ there, and Nihon-shiki writes ウィ, ウェ and
+    // クァ as whi, whe and qwa, as wi, we and kwa are ゐ, ゑ and くゎ. No input
+    // method spelling sets グァ apart from ぐゎ, so it is gwha, after wha.
+    katakana_table.insert("whi".to_string(), "\u{30A6}\u{30A3}".to_string());
+    katakana_table.insert("whe".to_string(), "\u{30A6}\u{30A7}".to_string());
+    katakana_table.insert("twu".to_string(), "\u{30C8}\u{30A5}".to_string());
+    katakana_table.insert("dwu".to_string(), "\u{30C9}\u{30A5}".to_string());
+    katakana_table.insert("thi".to_string(), "\u{30C6}\u{30A3}".to_string());
+    katakana_table.insert("thu".to_string(), "\u{30C6}\u{30E5}".to_string());
+    katakana_table.insert("dhi".to_string(), "\u{30C7}\u{30A3}".to_string());
+    katakana_table.insert("dhu".to_string(), "\u{30C7}\u{30E5}".to_string());
+    katakana_table.insert("qwa".to_string(), "\u{30AF}\u{30A1}".to_string());
+    katakana_table.insert("gwha".to_string(), "\u{30B0}\u{30A1}".to_string());
+
 
     katakana_table
 }
 
 /// Returns a hashmap of kana characters to english syllables used for romanization
-pub fn initilize_roomaji(initialize_kana : fn()-> Vec<&'static str>) -> HashMap<String, String> {
+pub fn initialize_roomaji(initialize_kana : fn()-> Vec<&'static str>) -> HashMap<String, String> {
     let mut roomaji_table = HashMap::new();
     let kana_unicode = initialize_kana();
     let syllabary = initialize_japanese_syllables();
@@ -471,6 +770,15 @@ pub fn initilize_roomaji(initialize_kana : fn()-> Vec<&'static str>) -> HashMap<
         );
     }
 
+    // Nihon-shiki writes kwa and gwa with a small wa, as in くゎ, and the
+    // small kana written on their own are read as their full-size kana, so
+    // read those spellings back too, in this table's script.
+    let is_hiragana = roomaji_table.contains_key("\u{3042}");
+    for &(roomaji, hiragana, katakana) in NIHON_SHIKI_KANA.iter().chain(SMALL_KANA.iter()) {
+        let kana = if is_hiragana { hiragana } else { katakana };
+        roomaji_table.entry(kana.to_string()).or_insert_with(|| roomaji.to_string());
+    }
+
     roomaji_table
 
 }
\ No newline at end of file
@@ -0,0 +1,86 @@
+use std::cmp::Ordering;
+
+// The index is generated by build.rs from cmu_data/cmudict-0.7b.txt, see
+// that file for its layout.
+static WORDS: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/cmu_words.bin"));
+static PHONES: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/cmu_phones.bin"));
+static INDEX: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/cmu_index.bin"));
+static SYMBOLS: &str = include_str!(concat!(env!("OUT_DIR"), "/cmu_symbols.txt"));
+
+/// Separates the pronunciations of a headword in `PHONES`.
+const VARIANT_SEPARATOR: u8 = 255;
+
+/// The map from CMU phones to Japanese-ready phones. It is small enough to be
+/// embedded and parsed as is.
+pub static JAP_PHONES: &str =
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/cmu_data/cmuphones.txt"));
+
+/// The CMU phonetic dictionary compiled into the binary.
+///
+/// Lookups binary search the sorted headwords in place, so nothing but the
+/// phone symbols is parsed when the index is created.
+#[derive(Debug)]
+pub struct EmbeddedIndex {
+    symbols: Vec<&'static str>,
+}
+
+impl EmbeddedIndex {
+    pub fn new() -> EmbeddedIndex {
+        EmbeddedIndex {
+            symbols: SYMBOLS.lines().collect(),
+        }
+    }
+
+    /// Returns the pronunciations of an uppercase headword as whitespace
+    /// separated CMU phones, or an empty vector if it is not in the index.
+    pub fn get(&self, word: &str) -> Vec<String> {
+        let key = word.as_bytes();
+        let (mut low, mut high) = (0, entry_count());
+        while low < high {
+            let middle = (low + high) / 2;
+            match headword(middle).cmp(key) {
+                Ordering::Less => low = middle + 1,
+                Ordering::Greater => high = middle,
+                Ordering::Equal => return self.pronunciations(middle),
+            }
+        }
+        Vec::new()
+    }
+
+    fn pronunciations(&self, entry: usize) -> Vec<String> {
+        PHONES[offset(entry, 1)..offset(entry + 1, 1)]
+            .split(|&code| code == VARIANT_SEPARATOR)
+            .map(|variant| {
+                let phones: Vec<&str> = variant
+                    .iter()
+                    .map(|&code| self.symbols[code as usize])
+                    .collect();
+                phones.join(" ")
+            })
+            .collect()
+    }
+}
+
+impl Default for EmbeddedIndex {
+    fn default() -> EmbeddedIndex {
+        EmbeddedIndex::new()
+    }
+}
+
+/// The index ends with an extra pair of offsets marking the ends of the blobs.
+fn entry_count() -> usize {
+    INDEX.len() / 8 - 1
+}
+
+/// Reads field `field` (0 for the headword, 1 for the phones) of the offset
+/// pair for `entry`.
+fn offset(entry: usize, field: usize) -> usize {
+    let start = entry * 8 + field * 4;
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&INDEX[start..start + 4]);
+    u32::from_le_bytes(bytes) as usize
+}
+
+fn headword(entry: usize) -> &'static [u8] {
+    &WORDS[offset(entry, 0)..offset(entry + 1, 0)]
+}
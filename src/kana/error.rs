@@ -0,0 +1,86 @@
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Errors returned by the transliteration functions.
+///
+/// Variants that come from a position in the input carry the byte offset of
+/// the offending fragment, so that `&input[position..position + fragment.len()]`
+/// is the part that could not be converted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KanaError {
+    /// A roomaji fragment that does not map to any kana.
+    UnknownSyllable { position: usize, fragment: String },
+    /// A character in kana input that has no roomaji equivalent.
+    UnmappableKana { position: usize, kana: String },
+    /// A small kana (ゃ, ゅ, ょ, ぇ, っ, ...) with nothing to attach to.
+    DanglingSmallKana { position: usize, kana: String },
+    /// An English word that is not in the CMU dictionary.
+    CmuWordMissing { word: String },
+    /// A pronunciation index past the last variant of a CMU dictionary word.
+    CmuVariantMissing { word: String, variant: usize },
+    /// A data file that could not be opened.
+    DataFileMissing { path: PathBuf },
+    /// A data file line that could not be read or split into a key and value.
+    MalformedData { path: PathBuf, line: usize },
+    /// A CMU phone with no Japanese-ready equivalent in `cmuphones.txt`.
+    UnknownPhone { phone: String },
+}
+
+impl KanaError {
+    /// Returns the byte offset of the error in the input, if it has one.
+    pub fn position(&self) -> Option<usize> {
+        match *self {
+            KanaError::UnknownSyllable { position, .. }
+            | KanaError::UnmappableKana { position, .. }
+            | KanaError::DanglingSmallKana { position, .. } => Some(position),
+            _ => None,
+        }
+    }
+
+    /// Returns the part of the input that caused the error, if it has one.
+    pub fn fragment(&self) -> Option<&str> {
+        match *self {
+            KanaError::UnknownSyllable { ref fragment, .. } => Some(fragment),
+            KanaError::UnmappableKana { ref kana, .. }
+            | KanaError::DanglingSmallKana { ref kana, .. } => Some(kana),
+            KanaError::CmuWordMissing { ref word }
+            | KanaError::CmuVariantMissing { ref word, .. } => Some(word),
+            KanaError::UnknownPhone { ref phone } => Some(phone),
+            KanaError::DataFileMissing { .. } | KanaError::MalformedData { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for KanaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            KanaError::UnknownSyllable { position, ref fragment } => {
+                write!(f, "unknown syllable \"{}\" at byte {}", fragment, position)
+            }
+            KanaError::UnmappableKana { position, ref kana } => {
+                write!(f, "unable to romanize \"{}\" at byte {}", kana, position)
+            }
+            KanaError::DanglingSmallKana { position, ref kana } => {
+                write!(f, "small kana \"{}\" at byte {} has nothing to attach to", kana, position)
+            }
+            KanaError::CmuWordMissing { ref word } => {
+                write!(f, "\"{}\" is not in the CMU dictionary", word)
+            }
+            KanaError::CmuVariantMissing { ref word, variant } => {
+                write!(f, "\"{}\" has no pronunciation {} in the CMU dictionary", word, variant)
+            }
+            KanaError::DataFileMissing { ref path } => {
+                write!(f, "data file not found: {}", path.display())
+            }
+            KanaError::MalformedData { ref path, line } => {
+                write!(f, "malformed data in {} at line {}", path.display(), line)
+            }
+            KanaError::UnknownPhone { ref phone } => {
+                write!(f, "CMU phone \"{}\" has no Japanese equivalent", phone)
+            }
+        }
+    }
+}
+
+impl Error for KanaError {}
@@ -0,0 +1,417 @@
+use std::collections::HashMap;
+
+use kana::error::KanaError;
+
+/// One phone of a CMU pronunciation on its way to being spelled in roomaji.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Phone {
+    /// The CMU symbol without its stress marker, for example `IY`.
+    pub cmu: String,
+    /// The stress marker of a vowel: 0 for unstressed, 1 for primary and 2
+    /// for secondary stress. Consonants have none.
+    pub stress: Option<u8>,
+    /// The Japanese-ready spelling, starting from the `cmuphones.txt` entry.
+    /// An empty spelling drops the phone.
+    pub jap: String,
+}
+
+impl Phone {
+    /// Splits the stress marker off a CMU phone such as `IY1` and looks up
+    /// its Japanese-ready spelling.
+    ///
+    /// # Errors
+    ///
+    /// Returns `KanaError::UnknownPhone` if the phone is not in `jap_map`.
+    pub fn parse(phone: &str, jap_map: &HashMap<String, String>) -> Result<Phone, KanaError> {
+        // Stress marker is always at end.
+        let (cmu, stress) = match phone.chars().last().and_then(|c| c.to_digit(10)) {
+            Some(stress) => (&phone[..phone.len() - 1], Some(stress as u8)),
+            None => (phone, None),
+        };
+        match jap_map.get(cmu) {
+            Some(jap) => Ok(Phone { cmu: cmu.to_string(), stress, jap: jap.to_string() }),
+            None => Err(KanaError::UnknownPhone { phone: cmu.to_string() }),
+        }
+    }
+
+    /// Returns true for vowels, which are the phones that carry stress.
+    pub fn is_vowel(&self) -> bool {
+        self.stress.is_some()
+    }
+
+    fn is_stressed(&self) -> bool {
+        self.stress.is_some_and(|stress| stress > 0)
+    }
+
+    /// Returns true if the spelling ends in a doubled vowel.
+    fn is_long(&self) -> bool {
+        let mut vowels = self.jap.chars().rev();
+        vowels.next().is_some_and(|last| vowels.next() == Some(last))
+    }
+
+    /// Doubles the final vowel of the spelling unless it is already long.
+    /// A doubled vowel becomes a choonpu in katakana. The diphthong ei is
+    /// lengthened to ee, as in ケーキ.
+    fn lengthen(&mut self) {
+        if self.jap.ends_with("EI") {
+            self.jap.pop();
+            self.jap.push('E');
+            return;
+        }
+        if let Some(last) = self.jap.chars().last().filter(|_| !self.is_long()) {
+            self.jap.push(last);
+        }
+    }
+}
+
+/// Vowels that are written long in loanwords when they carry stress, as in
+/// チーム (`T IY1 M`), ルール (`R UW1 L`), バード (`B ER1 D`) or ケーキ
+/// (`K EY1 K`). `EY` is only long before a consonant and stays エイ at the
+/// end of a word, as in ウェイ (`W EY1`).
+pub const STRESSED_LONG_VOWELS: &[&str] = &["IY", "UW", "AO", "OW", "ER", "EY"];
+
+/// Vowels that absorb a following r into a long vowel, as in カー (`K AA1 R`)
+/// or コード (`K AO1 R D`). Other vowels turn the r into a, as in ヘア
+/// (`HH EH1 R`).
+///
+/// `AA` is spelled o in `cmuphones.txt`, as in ホット (`HH AA1 T`), and
+/// short whatever its stress. Before an r it is written a instead.
+pub const R_LENGTHENED_VOWELS: &[&str] = &["AA", "AO", "OW"];
+
+/// Applies the long vowel conventions of loanwords using the stress markers
+/// of the CMU dictionary.
+///
+/// * Stressed tense vowels in `STRESSED_LONG_VOWELS` are lengthened.
+/// * An r that follows a vowel and does not start a syllable is dropped.
+///   Vowels in `R_LENGTHENED_VOWELS` are lengthened in its place; after any
+///   other vowel it is written a.
+/// * A word-final unstressed `ER` is lengthened, as in コンピューター.
+/// * `IH` before `NG` is written i rather than e, as in キング.
+pub fn lengthen_vowels(phones: &mut [Phone]) {
+    for i in 0..phones.len() {
+        let previous_is_vowel = i > 0 && phones[i - 1].is_vowel();
+        let next_is_vowel = phones.get(i + 1).is_some_and(Phone::is_vowel);
+        let is_last = i + 1 == phones.len();
+
+        if phones[i].is_vowel() {
+            if phones[i].cmu == "IH" && phones.get(i + 1).is_some_and(|next| next.cmu == "NG") {
+                phones[i].jap = "I".to_string();
+            }
+            let cmu = phones[i].cmu.as_str();
+            let stressed_long = phones[i].is_stressed()
+                && STRESSED_LONG_VOWELS.contains(&cmu)
+                && !(cmu == "EY" && is_last);
+            if stressed_long || (cmu == "ER" && is_last) {
+                phones[i].lengthen();
+            }
+        } else if phones[i].cmu == "R" && previous_is_vowel && !next_is_vowel {
+            if phones[i - 1].cmu == "ER" {
+                phones[i].jap.clear();
+            } else if R_LENGTHENED_VOWELS.contains(&phones[i - 1].cmu.as_str()) {
+                if phones[i - 1].cmu == "AA" {
+                    phones[i - 1].jap = "A".to_string();
+                }
+                phones[i - 1].lengthen();
+                phones[i].jap.clear();
+            } else {
+                phones[i].jap = "A".to_string();
+            }
+        }
+    }
+}
+
+/// Where in the word an `EpenthesisRule` applies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Position {
+    /// Any consonant that is not followed by a vowel.
+    Anywhere,
+    /// A consonant at the end of the word.
+    Final,
+    /// A consonant followed by another consonant.
+    BeforeConsonant,
+    /// A consonant followed by the given CMU phone.
+    Before(&'static str),
+}
+
+/// Chooses the vowel inserted after a consonant that has none, since
+/// Japanese syllables cannot end in a consonant other than n.
+///
+/// Rules are tried in order and the first match wins. A consonant that
+/// matches no rule gets `DEFAULT_EPENTHETIC_VOWEL`.
+///
+/// # Examples
+///
+/// ```
+/// use to_kana::kana::cmu::CmuDictionary;
+/// use to_kana::kana::loan::{default_epenthesis_rules, EpenthesisRule, Position};
+///
+/// let mut dictionary = CmuDictionary::load().unwrap();
+/// assert_eq!(dictionary.katakana("bulk").unwrap(), "バルク");
+///
+/// // Write a final k after l with i instead.
+/// let mut rules = default_epenthesis_rules();
+/// rules.insert(0, EpenthesisRule::new("K", Some("L"), Position::Final, "I"));
+/// dictionary.set_epenthesis_rules(rules);
+/// assert_eq!(dictionary.katakana("bulk").unwrap(), "バルキ");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpenthesisRule {
+    /// The CMU consonant the rule applies to, for example `T`.
+    pub consonant: String,
+    /// The CMU phone that must come right before the consonant, if any.
+    pub after: Option<String>,
+    /// Where the consonant must be.
+    pub position: Position,
+    /// The vowel to insert, in Japanese-ready spelling. An empty vowel
+    /// leaves the consonant as it is, which is how n and ng become ン.
+    pub vowel: String,
+}
+
+impl EpenthesisRule {
+    pub fn new(consonant: &str, after: Option<&str>, position: Position, vowel: &str) -> EpenthesisRule {
+        EpenthesisRule {
+            consonant: consonant.to_string(),
+            after: after.map(|after| after.to_string()),
+            position,
+            vowel: vowel.to_string(),
+        }
+    }
+
+    fn matches(&self, phones: &[Phone], i: usize) -> bool {
+        let is_last = i + 1 == phones.len();
+        let position_matches = match self.position {
+            Position::Anywhere => true,
+            Position::Final => is_last,
+            Position::BeforeConsonant => !is_last,
+            Position::Before(next) => phones.get(i + 1).is_some_and(|phone| phone.cmu == next),
+        };
+        let after_matches = match self.after {
+            Some(ref after) => i > 0 && &phones[i - 1].cmu == after,
+            None => true,
+        };
+        phones[i].cmu == self.consonant && position_matches && after_matches
+    }
+}
+
+/// The vowel inserted after consonants that match no `EpenthesisRule`, as
+/// in ラスト (`R AH1 S T`) where s becomes ス.
+pub const DEFAULT_EPENTHETIC_VOWEL: &str = "U";
+
+/// The epenthesis rules used by `CmuDictionary` unless it is given others:
+/// consonant, preceding phone, position and inserted vowel.
+pub const DEFAULT_EPENTHESIS: &[(&str, Option<&str>, Position, &str)] = &[
+    // The nasals are written ン on their own, but a final ng keeps its g, as
+    // in キング.
+    ("N", None, Position::Anywhere, ""),
+    ("NG", None, Position::Final, "GU"),
+    ("NG", None, Position::Anywhere, ""),
+    // m before p and b is left as it is, which the roomaji parser reads as
+    // ン, as in ジャンプ and コンピューター.
+    ("M", None, Position::Before("P"), ""),
+    ("M", None, Position::Before("B"), ""),
+    // t and d take o, since tu and du would be read as ツ and ヅ.
+    ("T", None, Position::Anywhere, "O"),
+    ("D", None, Position::Anywhere, "O"),
+    // The affricates take i, as in マッチ and ブリッジ.
+    ("CH", None, Position::Anywhere, "I"),
+    ("JH", None, Position::Anywhere, "I"),
+    // A final k after ei takes i, as in ケーキ and ステーキ.
+    ("K", Some("EY"), Position::Final, "I"),
+    // l takes u, as in バルク and ボール.
+    ("L", None, Position::Anywhere, "U"),
+];
+
+/// Returns `DEFAULT_EPENTHESIS` as a vector of rules that can be extended.
+pub fn default_epenthesis_rules() -> Vec<EpenthesisRule> {
+    DEFAULT_EPENTHESIS
+        .iter()
+        .map(|&(consonant, after, position, vowel)| EpenthesisRule::new(consonant, after, position, vowel))
+        .collect()
+}
+
+/// Inserts a vowel after every consonant that is not followed by a vowel,
+/// using the first matching rule in `rules`. Consonants already spelled with
+/// a vowel, such as an r written a by `lengthen_vowels`, are left alone.
+pub fn insert_vowels(phones: &mut [Phone], rules: &[EpenthesisRule]) {
+    for i in 0..phones.len() {
+        let next_is_vowel = phones.get(i + 1).is_some_and(Phone::is_vowel);
+        let spelled_with_vowel = phones[i].jap.ends_with(|c| "AEIOU".contains(c));
+        if phones[i].is_vowel() || next_is_vowel || spelled_with_vowel || phones[i].jap.is_empty() {
+            continue;
+        }
+        let vowel = match rules.iter().find(|rule| rule.matches(phones, i)) {
+            Some(rule) => rule.vowel.clone(),
+            None => DEFAULT_EPENTHETIC_VOWEL.to_string(),
+        };
+        phones[i].jap.push_str(&vowel);
+    }
+}
+
+/// Lax vowels and the short o of `AA`, which are heard as short in
+/// loanwords and so can be followed by a geminate consonant, as in ホット
+/// (`HH AA1 T`).
+pub const SHORT_VOWELS: &[&str] = &["AA", "AE", "AH", "EH", "IH", "UH"];
+
+/// Voiceless consonants that are doubled at the end of a word after any
+/// short vowel, as in キャット (`K AE1 T`) or ペット (`P EH1 T`).
+pub const VOICELESS_GEMINATES: &[&str] = &["P", "T", "K", "CH"];
+
+/// Voiced consonants that are doubled at the end of a word after a stressed
+/// short vowel, as in バッグ (`B AE1 G`) or ベッド (`B EH1 D`).
+pub const VOICED_GEMINATES: &[&str] = &["G", "D", "JH"];
+
+/// Marks the consonants that are written with a small tsu before them by
+/// doubling their first letter, which is how the roomaji parser spells a
+/// geminate.
+///
+/// A consonant is doubled when it follows a vowel in `SHORT_VOWELS` and
+/// ends the word, or is a k followed only by a final s as in タックス
+/// (`T AE1 K S`). Consonants in `VOICELESS_GEMINATES` are doubled after any
+/// short vowel and those in `VOICED_GEMINATES` only after a stressed one.
+pub fn geminate_consonants(phones: &mut [Phone]) {
+    for i in 1..phones.len() {
+        let is_last = i + 1 == phones.len();
+        let before_final_s = i + 2 == phones.len() && phones[i].cmu == "K" && phones[i + 1].cmu == "S";
+        if !(is_last || before_final_s) {
+            continue;
+        }
+
+        // A short vowel lengthened by an r, as in ハート, is long.
+        let vowel = &phones[i - 1];
+        if !SHORT_VOWELS.contains(&vowel.cmu.as_str()) || vowel.is_long() {
+            continue;
+        }
+        let consonant = phones[i].cmu.as_str();
+        if VOICELESS_GEMINATES.contains(&consonant)
+            || (vowel.is_stressed() && VOICED_GEMINATES.contains(&consonant))
+        {
+            if let Some(first) = phones[i].jap.chars().next() {
+                phones[i].jap.insert(0, first);
+            }
+        }
+    }
+}
+
+/// Respells a consonant and the phone after it as one onset, for the
+/// palatalized and labialized sequences of loanwords such as キャ, ウィ and
+/// イェ.
+///
+/// Rules are tried in order and the first match wins.
+///
+/// # Examples
+///
+/// ```
+/// use to_kana::kana::loan::OnsetRule;
+///
+/// // K AE is written キャ, as in キャット.
+/// let rule = OnsetRule::new("K", "AE", "KY", None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnsetRule {
+    /// The CMU consonant that starts the onset.
+    pub consonant: String,
+    /// The CMU phone that follows it, a vowel or the glide `Y`.
+    pub next: String,
+    /// The Japanese-ready spelling of the consonant. An empty spelling
+    /// drops it, as w is dropped before u.
+    pub onset: String,
+    /// A replacement spelling for the following phone. An empty spelling
+    /// drops it, which is how a `Y` glide merges into キュ.
+    pub nucleus: Option<String>,
+}
+
+impl OnsetRule {
+    pub fn new(consonant: &str, next: &str, onset: &str, nucleus: Option<&str>) -> OnsetRule {
+        OnsetRule {
+            consonant: consonant.to_string(),
+            next: next.to_string(),
+            onset: onset.to_string(),
+            nucleus: nucleus.map(|nucleus| nucleus.to_string()),
+        }
+    }
+}
+
+/// The onset rules used by `CmuDictionary` unless it is given others:
+/// consonant, following phone, onset spelling and nucleus spelling.
+pub const DEFAULT_ONSETS: &[(&str, &str, &str, Option<&str>)] = &[
+    // Velars before the ash vowel are palatalized, as in キャット and ギャップ.
+    ("K", "AE", "KY", None),
+    ("G", "AE", "GY", None),
+    // A consonant followed by the y glide forms a digraph, as in キュート,
+    // ミュージック, アニュアル and ピューレ.
+    ("K", "Y", "KY", Some("")),
+    ("F", "Y", "FY", Some("")),
+    ("G", "Y", "GY", Some("")),
+    ("P", "Y", "PY", Some("")),
+    ("B", "Y", "BY", Some("")),
+    ("V", "Y", "BY", Some("")),
+    ("M", "Y", "MY", Some("")),
+    ("N", "Y", "NY", Some("")),
+    ("HH", "Y", "HY", Some("")),
+    ("L", "Y", "RY", Some("")),
+    ("R", "Y", "RY", Some("")),
+    // t before i and u is written チ and ツ, as in チーム and ツール, rather
+    // than the ティ and トゥ that the roomaji parser reads ti and tu as.
+    ("T", "IY", "CH", None),
+    ("T", "UW", "TS", None),
+    // w before front and mid vowels is written with a small vowel, as in
+    // ウィンドウ, ウェブ, ウェイ and ウォーター.
+    ("W", "IH", "WH", Some("I")),
+    ("W", "IY", "WH", None),
+    ("W", "EH", "WH", None),
+    ("W", "EY", "WH", None),
+    ("W", "AO", "WH", None),
+    ("W", "OW", "WH", None),
+    // k before w is written ku and the w joins it, as in クイーン and
+    // クイック, after the w has taken its vowel.
+    ("K", "W", "KU", Some("")),
+    // w before u is silent, as in ウッド and ウーマン.
+    ("W", "UH", "", None),
+    ("W", "UW", "", None),
+    // y before a vowel joins it, as in イェス, ヤード and ユース. Before i it
+    // is silent, as in イースト.
+    ("Y", "EH", "Y", None),
+    ("Y", "AA", "Y", None),
+    ("Y", "AE", "Y", None),
+    ("Y", "AH", "Y", None),
+    ("Y", "AO", "Y", None),
+    ("Y", "ER", "Y", None),
+    ("Y", "OW", "Y", None),
+    ("Y", "UH", "Y", None),
+    ("Y", "UW", "Y", None),
+    ("Y", "IH", "", Some("I")),
+    ("Y", "IY", "", None),
+];
+
+/// Returns `DEFAULT_ONSETS` as a vector of rules that can be extended.
+pub fn default_onset_rules() -> Vec<OnsetRule> {
+    DEFAULT_ONSETS
+        .iter()
+        .map(|&(consonant, next, onset, nucleus)| OnsetRule::new(consonant, next, onset, nucleus))
+        .collect()
+}
+
+/// Respells onsets with the first matching rule in `rules`, then removes
+/// the phones that were dropped by it or by `lengthen_vowels`.
+///
+/// Onsets are respelled from the end of the word, so that a glide is
+/// spelled with the vowel after it before the consonant before it joins it,
+/// as in クイック (`K W IH1 K`).
+pub fn join_onsets(phones: &mut Vec<Phone>, rules: &[OnsetRule]) {
+    for i in (0..phones.len().saturating_sub(1)).rev() {
+        // A phone dropped by `lengthen_vowels` is skipped.
+        if phones[i].jap.is_empty() {
+            continue;
+        }
+        let rule = rules
+            .iter()
+            .find(|rule| phones[i].cmu == rule.consonant && phones[i + 1].cmu == rule.next);
+        if let Some(rule) = rule {
+            phones[i].jap = rule.onset.clone();
+            if let Some(ref nucleus) = rule.nucleus {
+                phones[i + 1].jap = nucleus.clone();
+            }
+        }
+    }
+    phones.retain(|phone| !phone.jap.is_empty());
+}
@@ -1,3 +1,21 @@
+//! Transliteration between roomaji, kana and English.
+
+/// English to kana through the CMU phonetic dictionary.
 pub mod cmu;
+/// Keystroke by keystroke conversion of roomaji to kana.
+pub mod compose;
+/// Builders for the syllable and kana lookup tables.
 pub mod conv;
-pub mod trans;
\ No newline at end of file
+/// The CMU dictionary compiled in by the `embedded-cmu` feature.
+#[cfg(feature = "embedded-cmu")]
+mod embedded;
+/// The error type returned by the conversion functions.
+pub mod error;
+/// Loanword spelling rules for CMU pronunciations.
+pub mod loan;
+/// Options for reading roomaji input and choosing how kana is romanized.
+pub mod options;
+/// Conversion between hiragana, katakana and half-width katakana.
+pub mod script;
+/// Conversion between roomaji and kana.
+pub mod trans;
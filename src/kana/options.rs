@@ -0,0 +1,221 @@
+/// Options for reading roomaji input with `to_hiragana_with` and
+/// `to_katakana_with`. The default reads modern Hepburn.
+///
+/// # Example
+///
+/// ```
+/// use to_kana::{to_hiragana_with, KanaOptions, RomanizationSystem};
+///
+/// let options = KanaOptions { system: RomanizationSystem::Kunrei, ..KanaOptions::default() };
+/// assert_eq!(to_hiragana_with("tikatetu", false, &options).unwrap(), "ちかてつ");
+/// ```
+///
+/// # Example
+///
+/// ```
+/// use to_kana::{to_hiragana_with, KanaOptions};
+///
+/// let options = KanaOptions { historical: true, ..KanaOptions::default() };
+/// assert_eq!(to_hiragana_with("wiwe", false, &options).unwrap(), "ゐゑ");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KanaOptions {
+    /// Reads wi and we as the historical ゐ and ゑ (ヰ and ヱ) instead of the
+    /// modern ウィ and ウェ.
+    pub historical: bool,
+    /// The romanization system the input is written in. Kunrei-shiki reads
+    /// ti, tu and tyu as ち, つ and ちゅ rather than ティ, トゥ and テュ, and
+    /// Nihon-shiki also reads di, du, dyu, kwa, gwa, wi and we as ぢ, づ, ぢゅ,
+    /// くゎ, ぐゎ, ゐ and ゑ. Spellings that no Hepburn syllable uses, such as
+    /// sya and zyo, are read in every system.
+    pub system: RomanizationSystem,
+    /// How a long o written with a macron or circumflex, as in Tōkyō, is
+    /// spelled in hiragana. Katakana always uses the choonpu.
+    pub long_o: LongO,
+    /// Reads the key sequences of input method (wāpuro) roomaji, such as xtu
+    /// and ltu for っ, xya for ゃ, nn for ん, - for ー, wyi for ゐ and dhi for
+    /// ディ. Any doubled consonant but n is a geminate, and ti, tu, di and du
+    /// are ち, つ, ぢ and づ, as in an input method.
+    pub wapuro: bool,
+    /// Writes katakana output in JIS X 0201 half-width katakana, with voiced
+    /// kana as two characters, as in ｶﾞｯｺｳ.
+    pub halfwidth: bool,
+}
+
+/// The hiragana spellings of a long o.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum LongO {
+    /// おう, as in とうきょう for Tōkyō, which most long o sounds are.
+    #[default]
+    Ou,
+    /// おお, as in おおさか for Ōsaka.
+    Oo,
+}
+
+/// The romanization systems that `to_roomaji_hiragana_with` and
+/// `to_roomaji_katakana_with` can write, and that `KanaOptions` can read.
+/// The default is Modified Hepburn.
+///
+/// # Example
+///
+/// ```
+/// use to_kana::{to_roomaji_hiragana_with, RomanizationSystem};
+///
+/// let input = "とうきょう";
+/// assert_eq!(to_roomaji_hiragana_with(input, RomanizationSystem::ModifiedHepburn).unwrap(), "tōkyō");
+/// assert_eq!(to_roomaji_hiragana_with(input, RomanizationSystem::Kunrei).unwrap(), "tôkyô");
+/// assert_eq!(to_roomaji_hiragana_with(input, RomanizationSystem::Passport).unwrap(), "tohkyoh");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum RomanizationSystem {
+    /// Modified Hepburn: shi, chi, tsu, long vowels with macrons (ō, ū),
+    /// n' before vowels and y, and the particles は, へ and を as wa, e and o.
+    #[default]
+    ModifiedHepburn,
+    /// Traditional Hepburn: as Modified Hepburn, but m before b, m and p,
+    /// n- before vowels and y, and を as wo.
+    TraditionalHepburn,
+    /// Kunrei-shiki (ISO 3602): si, ti, tu, sya, zya, long vowels with
+    /// circumflexes (ô, û), and the particles は, へ and を as wa, e and o.
+    Kunrei,
+    /// Nihon-shiki (ISO 3602 strict): as Kunrei-shiki, but ぢ and づ are di
+    /// and du, ゐ, ゑ and を are wi, we and wo, and particles are spelled as
+    /// they are written.
+    NihonShiki,
+    /// The Hepburn used for Japanese passports: long o is oh, other long
+    /// vowels are written short, m before b, m and p, and no apostrophes.
+    Passport,
+}
+
+impl RomanizationSystem {
+    /// Returns true for the Hepburn systems.
+    pub fn is_hepburn(self) -> bool {
+        match self {
+            RomanizationSystem::ModifiedHepburn
+            | RomanizationSystem::TraditionalHepburn
+            | RomanizationSystem::Passport => true,
+            RomanizationSystem::Kunrei | RomanizationSystem::NihonShiki => false,
+        }
+    }
+
+    /// Returns the spelling of a syllable in this system when it differs
+    /// from the Hepburn spelling in the roomaji tables.
+    pub fn respell(self, roomaji: &str) -> Option<&'static str> {
+        if self.is_hepburn() {
+            return match roomaji {
+                "dji" => Some("ji"),
+                "dzu" => Some("zu"),
+                "dja" => Some("ja"),
+                "dju" => Some("ju"),
+                "djo" => Some("jo"),
+                _ => None,
+            };
+        }
+        match roomaji {
+            "shi" => Some("si"),
+            "chi" => Some("ti"),
+            "tsu" => Some("tu"),
+            "fu" => Some("hu"),
+            "ji" => Some("zi"),
+            "sha" => Some("sya"),
+            "shu" => Some("syu"),
+            "sho" => Some("syo"),
+            "she" => Some("sye"),
+            "cha" => Some("tya"),
+            "chu" => Some("tyu"),
+            "cho" => Some("tyo"),
+            "che" => Some("tye"),
+            "ja" => Some("zya"),
+            "ju" => Some("zyu"),
+            "jo" => Some("zyo"),
+            "je" => Some("zye"),
+            // The extended kana that the tables spell as ti, tu and tyu, and
+            // in Nihon-shiki di, du, dyu, wi, we, kwa and gwa, would read back
+            // as the native kana, so they take the input method spellings
+            // instead.
+            "ti" => Some("thi"),
+            "tu" => Some("twu"),
+            "tyu" => Some("thu"),
+            "di" if self == RomanizationSystem::NihonShiki => Some("dhi"),
+            "du" if self == RomanizationSystem::NihonShiki => Some("dwu"),
+            "dyu" if self == RomanizationSystem::NihonShiki => Some("dhu"),
+            "wi" if self == RomanizationSystem::NihonShiki => Some("whi"),
+            "we" if self == RomanizationSystem::NihonShiki => Some("whe"),
+            "kwa" if self == RomanizationSystem::NihonShiki => Some("qwa"),
+            "gwa" if self == RomanizationSystem::NihonShiki => Some("gwha"),
+            "dji" if self == RomanizationSystem::NihonShiki => Some("di"),
+            "dzu" if self == RomanizationSystem::NihonShiki => Some("du"),
+            "dja" if self == RomanizationSystem::NihonShiki => Some("dya"),
+            "dju" if self == RomanizationSystem::NihonShiki => Some("dyu"),
+            "djo" if self == RomanizationSystem::NihonShiki => Some("dyo"),
+            "dji" => Some("zi"),
+            "dzu" => Some("zu"),
+            "dja" => Some("zya"),
+            "dju" => Some("zyu"),
+            "djo" => Some("zyo"),
+            _ => None,
+        }
+    }
+
+    /// Returns the spelling of the historical wa-row kana ゐ, ゑ and を,
+    /// given as wi, we or wo.
+    pub fn wa_row(self, roomaji: &str) -> &'static str {
+        let keeps_w = match self {
+            RomanizationSystem::TraditionalHepburn => roomaji == "wo",
+            RomanizationSystem::NihonShiki => true,
+            _ => false,
+        };
+        match roomaji {
+            "wi" if keeps_w => "wi",
+            "we" if keeps_w => "we",
+            "wo" if keeps_w => "wo",
+            "wi" => "i",
+            "we" => "e",
+            _ => "o",
+        }
+    }
+
+    /// Returns true if the particles は and へ are written as they are
+    /// pronounced, wa and e.
+    pub fn spells_particles(self) -> bool {
+        self != RomanizationSystem::NihonShiki
+    }
+
+    /// Returns the spelling of a long vowel, or None if `vowel` is not one
+    /// of a, i, u, e and o.
+    pub fn long_vowel(self, vowel: char) -> Option<&'static str> {
+        let long = match (self, vowel) {
+            (RomanizationSystem::Passport, 'o') => "oh",
+            (RomanizationSystem::Passport, 'a') => "a",
+            (RomanizationSystem::Passport, 'i') => "i",
+            (RomanizationSystem::Passport, 'u') => "u",
+            (RomanizationSystem::Passport, 'e') => "e",
+            (RomanizationSystem::Kunrei, 'a') | (RomanizationSystem::NihonShiki, 'a') => "\u{E2}",
+            (RomanizationSystem::Kunrei, 'i') | (RomanizationSystem::NihonShiki, 'i') => "\u{EE}",
+            (RomanizationSystem::Kunrei, 'u') | (RomanizationSystem::NihonShiki, 'u') => "\u{FB}",
+            (RomanizationSystem::Kunrei, 'e') | (RomanizationSystem::NihonShiki, 'e') => "\u{EA}",
+            (RomanizationSystem::Kunrei, 'o') | (RomanizationSystem::NihonShiki, 'o') => "\u{F4}",
+            (_, 'a') => "\u{101}",
+            (_, 'i') => "\u{12B}",
+            (_, 'u') => "\u{16B}",
+            (_, 'e') => "\u{113}",
+            (_, 'o') => "\u{14D}",
+            _ => return None,
+        };
+        Some(long)
+    }
+
+    /// Returns what is written between syllabic n and a following vowel or y.
+    pub fn n_separator(self) -> &'static str {
+        match self {
+            RomanizationSystem::TraditionalHepburn => "-",
+            RomanizationSystem::Passport => "",
+            _ => "'",
+        }
+    }
+
+    /// Returns true if syllabic n is written m before b, m and p.
+    pub fn labial_n(self) -> bool {
+        matches!(self, RomanizationSystem::TraditionalHepburn | RomanizationSystem::Passport)
+    }
+}
@@ -0,0 +1,510 @@
+use initialize_japanese_syllables;
+use HIRAGANA_KEYS;
+use KATAKANA_KEYS;
+
+/// The distance from each hiragana to the katakana in the same position,
+/// such as あ (U+3042) and ア (U+30A2).
+const KATAKANA_OFFSET: u32 = 0x60;
+
+/// Returns hiragana input with every hiragana written as the katakana in
+/// the same position, including ゔ as ヴ, the small ゕ and ゖ as ヵ and ヶ, and
+/// the iteration marks ゝ and ゞ as ヽ and ヾ. The choonpu (ー) is used by both
+/// scripts and is left as it is, as are kanji, Latin characters and
+/// punctuation.
+///
+/// # Example
+///
+/// ```
+/// use to_kana::hiragana_to_katakana;
+///
+/// assert_eq!(hiragana_to_katakana("ひらがな"), "ヒラガナ");
+/// assert_eq!(hiragana_to_katakana("らーめんと漢字、ABC"), "ラーメント漢字、ABC");
+/// ```
+///
+pub fn hiragana_to_katakana(input: &str) -> String {
+    input.chars().map(katakana_for).collect()
+}
+
+/// Returns katakana input with every katakana that has a hiragana written
+/// as that hiragana, the reverse of `hiragana_to_katakana`. The choonpu is
+/// left as it is, so コーヒー becomes こーひー. Katakana with no hiragana
+/// form, such as ヷ and ヺ, are left as they are, as are kanji, Latin
+/// characters and punctuation, including the middle dot (・).
+///
+/// # Example
+///
+/// ```
+/// use to_kana::katakana_to_hiragana;
+///
+/// assert_eq!(katakana_to_hiragana("カタカナ"), "かたかな");
+/// assert_eq!(katakana_to_hiragana("ヴァイオリン・コーヒー"), "ゔぁいおりん・こーひー");
+/// ```
+///
+pub fn katakana_to_hiragana(input: &str) -> String {
+    input.chars().map(hiragana_for).collect()
+}
+
+/// Returns the katakana for a hiragana, or the character unchanged.
+fn katakana_for(c: char) -> char {
+    match c {
+        // From the small あ to the small け, and the iteration marks
+        '\u{3041}'..='\u{3096}' | '\u{309D}' | '\u{309E}' => {
+            ::std::char::from_u32(c as u32 + KATAKANA_OFFSET).unwrap_or(c)
+        }
+        _ => c,
+    }
+}
+
+/// Returns the hiragana for a katakana, or the character unchanged.
+fn hiragana_for(c: char) -> char {
+    match c {
+        '\u{30A1}'..='\u{30F6}' | '\u{30FD}' | '\u{30FE}' => {
+            ::std::char::from_u32(c as u32 - KATAKANA_OFFSET).unwrap_or(c)
+        }
+        _ => c,
+    }
+}
+
+/// The full-width forms of the JIS X 0201 half-width katakana and
+/// punctuation, from the half-width full stop (U+FF61) to the half-width
+/// handakuten (U+FF9F).
+const HALFWIDTH_KATAKANA: &str = "。「」、・ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチツテト\
+    ナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン゛゜";
+
+/// The first half-width character in `HALFWIDTH_KATAKANA`.
+const HALFWIDTH_START: u32 = 0xFF61;
+
+/// The half-width voicing marks, which follow the kana they voice.
+const HALFWIDTH_DAKUTEN: char = '\u{FF9E}';
+const HALFWIDTH_HANDAKUTEN: char = '\u{FF9F}';
+
+/// The combining and spacing voicing marks, which follow the kana they voice.
+const COMBINING_DAKUTEN: char = '\u{3099}';
+const COMBINING_HANDAKUTEN: char = '\u{309A}';
+const SPACING_DAKUTEN: char = '\u{309B}';
+const SPACING_HANDAKUTEN: char = '\u{309C}';
+
+/// Returns true for a combining, spacing or half-width voicing mark.
+pub(crate) fn is_voicing_mark(c: char) -> bool {
+    matches!(
+        c,
+        COMBINING_DAKUTEN | COMBINING_HANDAKUTEN | SPACING_DAKUTEN | SPACING_HANDAKUTEN
+        | HALFWIDTH_DAKUTEN | HALFWIDTH_HANDAKUTEN
+    )
+}
+
+/// Voiced kana that are not in the syllable tables: ゝ and ヽ, and the
+/// katakana wa-row.
+const OTHER_DAKUTEN: [(char, char); 6] = [
+    ('\u{309D}', '\u{309E}'),
+    ('\u{30FD}', '\u{30FE}'),
+    ('\u{30EF}', '\u{30F7}'),
+    ('\u{30F0}', '\u{30F8}'),
+    ('\u{30F1}', '\u{30F9}'),
+    ('\u{30F2}', '\u{30FA}'),
+];
+
+lazy_static! {
+    /// Kana and their forms with the dakuten, in both scripts.
+    static ref DAKUTEN: Vec<(char, char)> = {
+        let mut pairs = voicing_pairs(&[("ka", "ga"), ("sa", "za"), ("ta", "da"), ("ha", "ba")], 5);
+        pairs.extend(voicing_pairs(&[("u", "vu")], 1));
+        pairs.extend(OTHER_DAKUTEN.iter().cloned());
+        pairs
+    };
+    /// Kana and their forms with the handakuten, in both scripts.
+    static ref HANDAKUTEN: Vec<(char, char)> = voicing_pairs(&[("ha", "pa")], 5);
+}
+
+/// Returns input with JIS X 0201 half-width katakana and punctuation written
+/// in full width. A half-width dakuten or handakuten is composed with the
+/// kana before it, so ｶﾞ becomes ガ, and is written as the full-width ゛ or ゜
+/// when that kana cannot take it. Everything else is left as it is.
+///
+/// # Example
+///
+/// ```
+/// use to_kana::halfwidth_to_katakana;
+///
+/// assert_eq!(halfwidth_to_katakana("ｶﾀｶﾅ ｶﾞｯｺｳ｡"), "カタカナ ガッコウ。");
+/// ```
+///
+pub fn halfwidth_to_katakana(input: &str) -> String {
+    normalize(input, true, false).0
+}
+
+/// Returns input with full-width katakana, the choonpu and the punctuation
+/// of JIS X 0201 written in half width, with voiced kana split into the kana
+/// and a half-width voicing mark, so ガ becomes ｶﾞ. Katakana that have no
+/// half-width form, such as the small ヮ and ヵ, and everything else are
+/// left as they are.
+///
+/// # Example
+///
+/// ```
+/// use to_kana::katakana_to_halfwidth;
+///
+/// assert_eq!(katakana_to_halfwidth("ガッコウ・パーティー"), "ｶﾞｯｺｳ･ﾊﾟｰﾃｨｰ");
+/// ```
+///
+pub fn katakana_to_halfwidth(input: &str) -> String {
+    let mut output = String::new();
+    for c in input.chars() {
+        let base = remove_voicing(c).and_then(|base| halfwidth_for(base).map(|half| (base, half)));
+        if let Some((base, half)) = base {
+            output.push(half);
+            output.push(if add_dakuten(base) == Some(c) { HALFWIDTH_DAKUTEN } else { HALFWIDTH_HANDAKUTEN });
+        } else {
+            output.push(halfwidth_for(c).unwrap_or(c));
+        }
+    }
+    output
+}
+
+/// Returns the voiceless and voiced kana of each pair of rows, given by
+/// their first syllables, from the syllables and kana keys laid out in the
+/// same order in `initialize_japanese_syllables` and the key tables.
+fn voicing_pairs(rows: &[(&str, &str)], length: usize) -> Vec<(char, char)> {
+    let syllables = initialize_japanese_syllables();
+    let start = |syllable: &str| syllables.iter().position(|s| s == syllable).unwrap();
+    let mut pairs = Vec::new();
+    for &(voiceless, voiced) in rows {
+        let (voiceless, voiced) = (start(voiceless), start(voiced));
+        for keys in &[&*HIRAGANA_KEYS, &*KATAKANA_KEYS] {
+            let kana = |index: usize| keys[index].chars().next().unwrap();
+            for i in 0..length {
+                pairs.push((kana(voiceless + i), kana(voiced + i)));
+            }
+        }
+    }
+    pairs
+}
+
+/// Returns the kana with the dakuten, as が for か, or None if it cannot take
+/// one.
+///
+/// # Example
+///
+/// ```
+/// use to_kana::add_dakuten;
+///
+/// assert_eq!(add_dakuten('か'), Some('が'));
+/// assert_eq!(add_dakuten('ウ'), Some('ヴ'));
+/// assert_eq!(add_dakuten('な'), None);
+/// ```
+///
+pub fn add_dakuten(kana: char) -> Option<char> {
+    DAKUTEN.iter().find(|pair| pair.0 == kana).map(|pair| pair.1)
+}
+
+/// Returns the kana with the handakuten, as ぱ for は, or None if it cannot
+/// take one.
+///
+/// # Example
+///
+/// ```
+/// use to_kana::add_handakuten;
+///
+/// assert_eq!(add_handakuten('は'), Some('ぱ'));
+/// assert_eq!(add_handakuten('か'), None);
+/// ```
+///
+pub fn add_handakuten(kana: char) -> Option<char> {
+    HANDAKUTEN.iter().find(|pair| pair.0 == kana).map(|pair| pair.1)
+}
+
+/// Returns the kana without its dakuten or handakuten, as か for が and は for
+/// ぱ, or None if it has neither.
+///
+/// # Example
+///
+/// ```
+/// use to_kana::remove_voicing;
+///
+/// assert_eq!(remove_voicing('が'), Some('か'));
+/// assert_eq!(remove_voicing('ポ'), Some('ホ'));
+/// assert_eq!(remove_voicing('か'), None);
+/// ```
+///
+pub fn remove_voicing(kana: char) -> Option<char> {
+    DAKUTEN.iter().chain(HANDAKUTEN.iter()).find(|pair| pair.1 == kana).map(|pair| pair.0)
+}
+
+/// Returns input with each kana followed by a combining (U+3099, U+309A) or
+/// spacing (゛, ゜) voicing mark written as the voiced kana, so か with a
+/// combining dakuten becomes が. Marks that follow anything that cannot take
+/// them are left as they are.
+///
+/// # Example
+///
+/// ```
+/// use to_kana::compose_voicing;
+///
+/// assert_eq!(compose_voicing("\u{304B}\u{3099}\u{306F}\u{309C}"), "がぱ");
+/// ```
+///
+pub fn compose_voicing(input: &str) -> String {
+    normalize(input, false, true).0
+}
+
+/// Returns input with each voiced kana written as the kana and a combining
+/// voicing mark (U+3099 or U+309A), as in file names on macOS. This is the
+/// reverse of `compose_voicing`.
+///
+/// # Example
+///
+/// ```
+/// use to_kana::decompose_voicing;
+///
+/// assert_eq!(decompose_voicing("がぱ"), "\u{304B}\u{3099}\u{306F}\u{309A}");
+/// ```
+///
+pub fn decompose_voicing(input: &str) -> String {
+    let mut output = String::new();
+    for c in input.chars() {
+        match remove_voicing(c) {
+            Some(base) => {
+                output.push(base);
+                output.push(if add_dakuten(base) == Some(c) { COMBINING_DAKUTEN } else { COMBINING_HANDAKUTEN });
+            }
+            None => output.push(c),
+        }
+    }
+    output
+}
+
+/// Writes half-width katakana in full width, composes voicing marks and
+/// expands iteration marks, as `halfwidth_to_katakana`, `compose_voicing`
+/// and `expand_iteration_marks` do, so that kana can be looked up in the
+/// roomaji tables. Returns the byte offset in the input of each character of
+/// the output, followed by the length of the input.
+pub(crate) fn normalize_kana(input: &str) -> (String, Vec<usize>) {
+    let (normalized, origins) = normalize(input, true, true);
+    // Expanding iteration marks writes one character for each mark.
+    (expand_iteration_marks(&normalized), origins)
+}
+
+/// Writes half-width katakana in full width if `halfwidth` is set, and
+/// composes combining and spacing voicing marks if `voicing` is set, with
+/// the byte offset in the input of each character of the output.
+fn normalize(input: &str, halfwidth: bool, voicing: bool) -> (String, Vec<usize>) {
+    let mut output = String::new();
+    let mut origins = Vec::new();
+    for (position, c) in input.char_indices() {
+        let full = if halfwidth { fullwidth_for(c).unwrap_or(c) } else { c };
+        let add: Option<fn(char) -> Option<char>> = match c {
+            HALFWIDTH_DAKUTEN if halfwidth => Some(add_dakuten),
+            HALFWIDTH_HANDAKUTEN if halfwidth => Some(add_handakuten),
+            COMBINING_DAKUTEN | SPACING_DAKUTEN if voicing => Some(add_dakuten),
+            COMBINING_HANDAKUTEN | SPACING_HANDAKUTEN if voicing => Some(add_handakuten),
+            _ => None,
+        };
+        match add.and_then(|add| output.chars().last().and_then(add)) {
+            // The voiced kana starts where its unvoiced kana did.
+            Some(voiced) => {
+                output.pop();
+                output.push(voiced);
+            }
+            None => {
+                output.push(full);
+                origins.push(position);
+            }
+        }
+    }
+    origins.push(input.len());
+    (output, origins)
+}
+
+/// Returns the full-width form of a half-width katakana or punctuation mark.
+fn fullwidth_for(c: char) -> Option<char> {
+    (c as u32).checked_sub(HALFWIDTH_START)
+        .and_then(|index| HALFWIDTH_KATAKANA.chars().nth(index as usize))
+}
+
+/// Returns the half-width form of a full-width katakana or punctuation mark.
+fn halfwidth_for(c: char) -> Option<char> {
+    HALFWIDTH_KATAKANA.chars()
+        .position(|full| full == c)
+        .and_then(|index| ::std::char::from_u32(HALFWIDTH_START + index as u32))
+}
+
+/// The kana iteration marks, which repeat the kana before them without and
+/// with the dakuten, and the kanji iteration mark.
+const HIRAGANA_ITERATION: char = '\u{309D}';
+const HIRAGANA_VOICED_ITERATION: char = '\u{309E}';
+const KATAKANA_ITERATION: char = '\u{30FD}';
+const KATAKANA_VOICED_ITERATION: char = '\u{30FE}';
+const KANJI_ITERATION: char = '\u{3005}';
+
+/// Returns input with each iteration mark written as the character it
+/// repeats. ゝ and ヽ repeat the kana before them without the dakuten, and ゞ
+/// and ヾ repeat it with the dakuten, so いすゞ becomes いすず and こゝろ
+/// becomes こころ. 々 repeats the kanji before it. Marks that do not follow a
+/// kana or kanji are left as they are.
+///
+/// # Example
+///
+/// ```
+/// use to_kana::expand_iteration_marks;
+///
+/// assert_eq!(expand_iteration_marks("いすゞ こゝろ ぶゝ 時々"), "いすず こころ ぶふ 時時");
+/// ```
+///
+pub fn expand_iteration_marks(input: &str) -> String {
+    let mut output = String::new();
+    let mut previous = None;
+    for c in input.chars() {
+        let c = previous.and_then(|previous| repeated(previous, c)).unwrap_or(c);
+        output.push(c);
+        previous = Some(c);
+    }
+    output
+}
+
+/// Returns input with each kana that repeats the kana before it written as
+/// an iteration mark in the same script, and each kanji that repeats the
+/// kanji before it written as 々. This is the reverse of
+/// `expand_iteration_marks`. A character written as a mark is not repeated
+/// by the next one, so こここ becomes こゝこ.
+///
+/// # Example
+///
+/// ```
+/// use to_kana::collapse_iteration_marks;
+///
+/// assert_eq!(collapse_iteration_marks("いすず ココロ 時時"), "いすゞ コヽロ 時々");
+/// ```
+///
+pub fn collapse_iteration_marks(input: &str) -> String {
+    let mut output = String::new();
+    let mut previous = None;
+    for c in input.chars() {
+        let mark = previous.and_then(|previous| iteration_mark(previous, c));
+        output.push(mark.unwrap_or(c));
+        previous = if mark.is_some() { None } else { Some(c) };
+    }
+    output
+}
+
+/// Returns the character an iteration mark stands for after `previous`, or
+/// None if `mark` is not an iteration mark that can follow it.
+fn repeated(previous: char, mark: char) -> Option<char> {
+    let base = remove_voicing(previous).unwrap_or(previous);
+    match mark {
+        HIRAGANA_ITERATION | KATAKANA_ITERATION if is_kana(previous) => Some(base),
+        HIRAGANA_VOICED_ITERATION | KATAKANA_VOICED_ITERATION if is_kana(previous) => {
+            Some(add_dakuten(base).unwrap_or(base))
+        }
+        KANJI_ITERATION if is_kanji(previous) => Some(previous),
+        _ => None,
+    }
+}
+
+/// Returns the iteration mark that stands for `c` after `previous`, or None
+/// if `c` does not repeat it.
+fn iteration_mark(previous: char, c: char) -> Option<char> {
+    if is_kanji(c) {
+        return if c == previous { Some(KANJI_ITERATION) } else { None };
+    }
+    let base = remove_voicing(c).unwrap_or(c);
+    if !is_kana(c) || !is_kana(previous) || remove_voicing(previous).unwrap_or(previous) != base {
+        return None;
+    }
+    let hiragana = c < '\u{30A0}';
+    if c == base {
+        Some(if hiragana { HIRAGANA_ITERATION } else { KATAKANA_ITERATION })
+    } else if add_dakuten(base) == Some(c) {
+        Some(if hiragana { HIRAGANA_VOICED_ITERATION } else { KATAKANA_VOICED_ITERATION })
+    } else {
+        None
+    }
+}
+
+/// Returns true for hiragana and katakana, but not for the choonpu or the
+/// iteration marks.
+fn is_kana(c: char) -> bool {
+    matches!(c, '\u{3041}'..='\u{3096}' | '\u{30A1}'..='\u{30FA}')
+}
+
+/// Returns true for the CJK unified ideographs and their first extension.
+fn is_kanji(c: char) -> bool {
+    matches!(c, '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}')
+}
+
+#[test]
+fn test_hiragana_to_katakana_small_and_voiced_kana() {
+    assert_eq!("キャッヴァヵヶヽヾ", hiragana_to_katakana("きゃっゔぁゕゖゝゞ"));
+}
+
+#[test]
+fn test_katakana_to_hiragana_leaves_other_characters() {
+    assert_eq!("ちーず 東京 ヷ abc。", katakana_to_hiragana("チーズ 東京 ヷ abc。"));
+}
+
+#[test]
+fn test_script_round_trip() {
+    let hiragana: String = (0x3041..0x3097).chain(0x309D..0x309F)
+        .filter_map(::std::char::from_u32)
+        .collect();
+    assert_eq!(hiragana, katakana_to_hiragana(&hiragana_to_katakana(&hiragana)));
+}
+
+#[test]
+fn test_halfwidth_table_covers_jis_x_0201() {
+    assert_eq!(0xFF9F - HALFWIDTH_START as usize + 1, HALFWIDTH_KATAKANA.chars().count());
+    assert_eq!(Some('ン'), fullwidth_for('\u{FF9D}'));
+    assert_eq!(Some('\u{FF70}'), halfwidth_for('ー'));
+}
+
+#[test]
+fn test_halfwidth_voicing_marks() {
+    assert_eq!("ヴァパパ゛ア゜", halfwidth_to_katakana("ｳﾞｧﾊﾟﾊﾟﾞｱﾟ"));
+    let (normalized, origins) = normalize("ｶﾞｷ", true, false);
+    assert_eq!("ガキ", normalized);
+    assert_eq!(vec![0, 6, 9], origins);
+}
+
+#[test]
+fn test_halfwidth_round_trip() {
+    let katakana = "ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホ\
+        マミムメモヤユヨラリルレロワンガギグゲゴザジズゼゾダヂヅデドバビブベボパピプペポヴヷヺ";
+    assert_eq!(katakana, halfwidth_to_katakana(&katakana_to_halfwidth(katakana)));
+    assert_eq!("ヮヵヶ", katakana_to_halfwidth("ヮヵヶ"));
+}
+
+#[test]
+fn test_voicing_pairs_from_key_tables() {
+    assert_eq!(Some('\u{3065}'), add_dakuten('\u{3064}'));
+    assert_eq!(Some('\u{30D7}'), add_handakuten('\u{30D5}'));
+    assert_eq!(Some('\u{3094}'), add_dakuten('\u{3046}'));
+    assert_eq!(Some('\u{30FA}'), add_dakuten('\u{30F2}'));
+    assert_eq!(None, add_handakuten('\u{3070}'));
+    assert_eq!(None, remove_voicing('\u{3042}'));
+    for &(voiceless, voiced) in DAKUTEN.iter().chain(HANDAKUTEN.iter()) {
+        assert_eq!(Some(voiceless), remove_voicing(voiced));
+    }
+}
+
+#[test]
+fn test_voicing_round_trip() {
+    let voiced = "がぎぐげござじずぜぞだぢづでどばびぶべぼぱぴぷぺぽゔゞヴヷヸヹヺヾ";
+    let decomposed = decompose_voicing(voiced);
+    assert_eq!(voiced.chars().count() * 2, decomposed.chars().count());
+    assert_eq!(voiced, compose_voicing(&decomposed));
+    assert_eq!("\u{3099}あ\u{3099}が", compose_voicing("\u{3099}あ\u{3099}か\u{309B}"));
+}
+
+#[test]
+fn test_expand_iteration_marks() {
+    assert_eq!("ガガ ぱば ヽ ab々 ななな", expand_iteration_marks("ガヾ ぱゞ ヽ ab々 なゝゝ"));
+}
+
+#[test]
+fn test_iteration_marks_round_trip() {
+    let inputs = ["こころ", "いすず", "ぶふ", "ばば", "ぱぱ", "ここここ", "人人", "ハハバ", "ーー"];
+    for input in &inputs {
+        assert_eq!(*input, expand_iteration_marks(&collapse_iteration_marks(input)));
+    }
+    assert_eq!("ぱぱ ーー", collapse_iteration_marks("ぱぱ ーー"));
+    assert_eq!("ばゞ ぶゝ", collapse_iteration_marks("ばば ぶふ"));
+}
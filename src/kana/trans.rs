@@ -1,44 +1,56 @@
+use std::collections::HashMap;
+
+use kana::conv::{HISTORICAL_KANA, KUNREI_KANA, NIHON_SHIKI_KANA, WAPURO_KANA};
+use kana::error::KanaError;
+use kana::options::{KanaOptions, LongO, RomanizationSystem};
+use kana::script::{is_voicing_mark, katakana_to_halfwidth, normalize_kana};
 use KATAKANA;
 use HIRAGANA;
 use ROOMAJI_HIRAGANA;
 use ROOMAJI_KATAKANA;
 
+/// The length of the longest roomaji syllable, such as the wāpuro xtsu.
+const MAX_SYLLABLE_LENGTH: usize = 4;
+
+/// The length of the longest kana sequence in the roomaji tables, such as
+/// きゃ or ファ.
+const MAX_KANA_LENGTH: usize = 2;
+
 /// Returns a vector of strings where each string represents a Japanese syllable
 ///
 /// #Arguments
 ///
 /// * `input` - A str slice that needs to be parsed into Japanese syllables
 /// * `is_eng` - A boolean value that determines whether or not the input is
-///               an english word
+///   an english word
 ///
 /// # Example
 ///
 /// ```
+/// use to_kana::to_japanese_syllables;
+///
 /// let test_input = "toto";
-/// assert_eq!(to_japanese_syllables(test_input, false), "["to", "to"]);
-/// 
-/// let test input = "grab";
-/// assert_eq!(to_japanese_syllables(test_input, true), "["g", "ra", "b"]);
+/// assert_eq!(to_japanese_syllables(test_input, false), ["to", "to"]);
+///
+/// let test_input = "grab";
+/// assert_eq!(to_japanese_syllables(test_input, true), ["g", "ra", "b"]);
 /// ```
 ///
 pub fn to_japanese_syllables(input: &str, is_eng: bool) -> Vec<String> {
+    split_syllables(input, is_eng, &KanaOptions::default())
+}
+
+/// Splits the input into syllables the way `to_japanese_syllables` does,
+/// also taking the syllables that `options` adds to the tables.
+fn split_syllables(input: &str, is_eng: bool, options: &KanaOptions) -> Vec<String> {
     let input = input.to_lowercase();
 
     // This vector is used to store the syllables for the input string
     let mut syllables = Vec::new();
     // Vowel array for comparison
     let vowels = ['a', 'e', 'i', 'o', 'u'];
-    // Possible geminate characters
-    let geminates = ['k', 't', 'p', 'g', 'd', 'b', 's', 'z', 'c'];
-    // Possible digraphs
-    let digraph = ["ky", "sh", "ch", "nq", "hy", "my", "ry", "gy", "by", "py"];
-
-    let mut temp_syllable = "".to_string();
-    let mut temp_digraph = "".to_string();
-    let mut prev_nasal = false;
-    let mut prev_geminate = false;
-
-        if is_eng {
+    if is_eng {
+        let mut temp_syllable = "".to_string();
         let mut prevchar = 'a';
 
         for c in input.chars(){
@@ -47,7 +59,7 @@ pub fn to_japanese_syllables(input: &str, is_eng: bool) -> Vec<String> {
             if vowels.contains(&c){
                 if !vowels.contains(&prevchar) {
                     syllables.pop();
-                    temp_syllable.insert_str(0, &prevchar.to_string());
+                    temp_syllable.insert(0, prevchar);
                     }
                 syllables.push(temp_syllable);
             } else {
@@ -58,77 +70,197 @@ pub fn to_japanese_syllables(input: &str, is_eng: bool) -> Vec<String> {
 
         }
     } else {
-    for c in input.chars() {
-        temp_syllable.push(c);
-        temp_digraph.push(c);
-
-        // This check adds non-alphabetic chars to the syllable vector.
-        if !c.is_alphabetic() {
-            prev_nasal = false;
-            syllables.push(c.to_string());
-            temp_syllable = "".to_string();
-        }
-
-        // This checks to see if it's a digraph
-        if digraph.contains(&temp_digraph.as_str()) {
-            if prev_geminate {
-                syllables.pop();
-            }
-            if prev_nasal {
-                syllables.pop();
+        let chars: Vec<char> = input.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            // Non-alphabetic chars are passed through as they are.
+            if !c.is_alphabetic() {
+                syllables.push(c.to_string());
+                i += 1;
+                continue;
             }
-            temp_syllable = "".to_string();
-            temp_syllable.push_str(&temp_digraph);
-            prev_geminate = false;
-            prev_nasal = false;
-        }
-        // Japanese syllables can end in final -n, so it needs to be checked.
-        if c == 'n' {
-            prev_nasal = true;
-            syllables.push(c.to_string());
-        } else if !vowels.contains(&c) && prev_nasal {
-            prev_nasal = false;
-            temp_syllable = "".to_string();
-            temp_syllable.push(c);
-        }
-        // This checks the geminate array and sets geminate flag to test for gemination.
-        if !vowels.contains(&c) && prev_geminate {
-            let temp_char = syllables.pop().unwrap();
-
-            if temp_char == c.to_string() {
-                temp_syllable = "".to_string();
+            // A doubled consonant is the flag for gemination followed by the
+            // syllable that starts with the second consonant.
+            if starts_geminate(&chars[i..], options) {
                 syllables.push("G".to_string());
-                temp_syllable.push(c);
-                prev_geminate = false;
+                i += 1;
                 continue;
             }
-        }
-        if geminates.contains(&c) {
-            prev_geminate = true;
-            syllables.push(c.to_string());
-        }
-
-        if vowels.contains(&c) {
-            if prev_geminate {
-                syllables.pop();
+            if starts_labial_n(&chars[i..], options) {
+                syllables.push("n".to_string());
+                i += 1;
+                continue;
             }
-
-            if prev_nasal {
-                syllables.pop();
-                prev_nasal = false;
+            // Before a vowel or y, nn is ん followed by a syllable that starts
+            // with n, as in konnichiwa, except in wāpuro input.
+            if starts_doubled_n(&chars[i..], options) {
+                syllables.push("n".to_string());
+                i += 1;
+                continue;
             }
-            prev_geminate = false;
-            syllables.push(temp_syllable);
-            temp_syllable = "".to_string();
+            // Otherwise the longest syllable in the table wins, so tsa is
+            // read as one syllable rather than ts + a, and n' is final n.
+            // The lone consonants are only there for English input.
+            let longest = (1..MAX_SYLLABLE_LENGTH + 1)
+                .rev()
+                .filter(|&length| i + length <= chars.len())
+                .map(|length| chars[i..i + length].iter().collect::<String>())
+                .find(|syllable| {
+                    HIRAGANA.contains_key(syllable) && !is_lone_consonant(syllable)
+                        || mode_kana(syllable, options).is_some()
+                });
+            let syllable = match longest {
+                Some(syllable) => syllable,
+                // Unknown syllables run to the next vowel so that the error
+                // quotes the whole of them.
+                None => {
+                    let mut end = i + 1;
+                    while end < chars.len()
+                        && !vowels.contains(&chars[end - 1])
+                        && chars[end].is_alphabetic()
+                    {
+                        end += 1;
+                    }
+                    chars[i..end].iter().collect()
+                }
+            };
+            i += syllable.chars().count();
+            syllables.push(syllable);
         }
+    }
+    syllables
+}
 
-        if temp_digraph.len() >= 2 {
-            temp_digraph = "".to_string();
-            temp_digraph.push(c);
-        }
+/// Returns true for the keys of the tables that are a consonant with no
+/// vowel, such as k and sh, which stand for English consonants. The final n,
+/// its spellings n' and nn, and the gemination flag are not lone consonants.
+fn is_lone_consonant(syllable: &str) -> bool {
+    syllable.chars().all(|c| c.is_ascii_lowercase() && !"aiueon".contains(c))
+}
+
+/// Consonants that are doubled to mark a geminate.
+const GEMINATES: [char; 12] = ['k', 't', 'p', 'g', 'd', 'b', 's', 'z', 'c', 'j', 'f', 'v'];
+
+/// Returns true if the lowercase roomaji in `chars` starts with a consonant
+/// that is doubled to mark a geminate. Wāpuro input doubles any consonant
+/// but n, and Hepburn doubles ch as tch, as in matcha and kotchi.
+pub(crate) fn starts_geminate(chars: &[char], options: &KanaOptions) -> bool {
+    let (first, rest) = match chars.split_first() {
+        Some(split) => split,
+        None => return false,
+    };
+    let doubles = GEMINATES.contains(first)
+        || options.wapuro && first.is_alphabetic() && !"aiueon".contains(*first);
+    doubles && rest.first() == Some(first) || *first == 't' && rest.starts_with(&['c', 'h'])
+}
+
+/// Returns true if the lowercase roomaji in `chars` starts with final n
+/// written as m before b, p and m, as Hepburn does in shimbun. Wāpuro input
+/// reads mm as a geminate instead.
+pub(crate) fn starts_labial_n(chars: &[char], options: &KanaOptions) -> bool {
+    match chars {
+        ['m', 'm', ..] => !options.wapuro,
+        ['m', next, ..] => "bp".contains(*next),
+        _ => false,
     }
+}
+
+/// Returns true if the lowercase roomaji in `chars` starts with final n
+/// written nn before a vowel or y, which is final n followed by a syllable
+/// that starts with n, as in konnichiwa. Wāpuro input reads nn as final n
+/// on its own.
+pub(crate) fn starts_doubled_n(chars: &[char], options: &KanaOptions) -> bool {
+    match chars {
+        ['n', 'n', next, ..] => !options.wapuro && "aiueoy".contains(*next),
+        _ => false,
     }
-    syllables
+}
+
+/// Returns the char index in `input` where each syllable starts.
+///
+/// The syllables produced by `to_japanese_syllables` spell out the input one
+/// character at a time, except for the gemination flag "G" which stands in for
+/// the first of the doubled consonants.
+fn syllable_offsets(input: &str, syllables: &[String]) -> Vec<usize> {
+    let mut offsets = Vec::new();
+    let mut offset = 0;
+    for syllable in syllables {
+        offsets.push(offset);
+        offset += if syllable == "G" { 1 } else { syllable.chars().count() };
+    }
+    offsets.push(input.chars().count());
+    offsets
+}
+
+/// Writes out the long vowels of roomaji input that are marked with a macron
+/// or circumflex, precomposed or combining, as two vowels: ō becomes ou or oo
+/// as `long_o` asks, and the rest are doubled. Everything else is lowercased
+/// here, one char at a time, as a char such as İ can lowercase to more than
+/// one char.
+///
+/// Returns the expanded input and the byte offset in `input` of each of its
+/// chars, followed by the length of `input`.
+fn expand_long_vowels(input: &str, long_o: char) -> (String, Vec<usize>) {
+    let mut expanded = String::new();
+    let mut origins = Vec::new();
+    let mut chars = input.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let vowel = match long_vowel_base(c) {
+            Some(vowel) => Some(vowel),
+            None if "aiueoAIUEO".contains(c) => {
+                // A combining macron or circumflex
+                match chars.peek() {
+                    Some(&(_, '\u{304}')) | Some(&(_, '\u{302}')) => {
+                        chars.next();
+                        Some(c)
+                    }
+                    _ => None,
+                }
+            }
+            None => None,
+        };
+        match vowel {
+            Some(vowel) => {
+                let second = match vowel {
+                    'o' => long_o,
+                    'O' => long_o.to_ascii_uppercase(),
+                    _ => vowel,
+                };
+                expanded.push(vowel);
+                expanded.push(second);
+                origins.push(i);
+                origins.push(i);
+            }
+            None => {
+                for lower in c.to_lowercase() {
+                    expanded.push(lower);
+                    origins.push(i);
+                }
+            }
+        }
+    }
+    origins.push(input.len());
+    (expanded, origins)
+}
+
+/// Returns the plain vowel of a precomposed vowel with a macron or
+/// circumflex.
+fn long_vowel_base(c: char) -> Option<char> {
+    let vowel = match c {
+        '\u{101}' | '\u{E2}' => 'a',
+        '\u{12B}' | '\u{EE}' => 'i',
+        '\u{16B}' | '\u{FB}' => 'u',
+        '\u{113}' | '\u{EA}' => 'e',
+        '\u{14D}' | '\u{F4}' => 'o',
+        '\u{100}' | '\u{C2}' => 'A',
+        '\u{12A}' | '\u{CE}' => 'I',
+        '\u{16A}' | '\u{DB}' => 'U',
+        '\u{112}' | '\u{CA}' => 'E',
+        '\u{14C}' | '\u{D4}' => 'O',
+        _ => return None,
+    };
+    Some(vowel)
 }
 
 ///  Returns a result that gives a string in hiragana on success.
@@ -138,27 +270,66 @@ pub fn to_japanese_syllables(input: &str, is_eng: bool) -> Vec<String> {
 /// * `input` - A string slice that will be converted to hiragana
 /// * `is_eng` - A boolean value that is set if the input origin is from English
 ///
+/// # Errors
+///
+/// Returns `KanaError::UnknownSyllable` with the position of the first
+/// syllable that has no hiragana.
+///
 ///  # Examples
 ///
 ///  ```
+///  use to_kana::to_hiragana;
+///
 ///  let input = "kana";
-///  assert_eq!(to_hiragana(input).unwrap(), "かな");
-///  ````
+///  assert_eq!(to_hiragana(input, false).unwrap(), "かな");
+///  ```
+///
+pub fn to_hiragana(input: &str, is_eng: bool) -> Result<String, KanaError> {
+    to_hiragana_with(input, is_eng, &KanaOptions::default())
+}
+
+///  Returns a result that gives a string in hiragana on success, reading the
+/// input as set out by `options`.
+///
+/// # Errors
+///
+/// Returns `KanaError::UnknownSyllable` with the position of the first
+/// syllable that has no hiragana.
 ///
-pub fn to_hiragana(input: &str, is_eng: bool) -> Result<String, String> {
+///  # Examples
+///
+///  ```
+///  use to_kana::{to_hiragana_with, KanaOptions};
+///
+///  let options = KanaOptions { historical: true, ..KanaOptions::default() };
+///  assert_eq!(to_hiragana_with("wiwo", false, &options).unwrap(), "ゐを");
+///  ```
+///
+pub fn to_hiragana_with(input: &str, is_eng: bool, options: &KanaOptions)
+        -> Result<String, KanaError> {
     let mut output = "".to_string();
-    let syllables = to_japanese_syllables(input, is_eng);
+    let long_o = match options.long_o {
+        LongO::Ou => 'u',
+        LongO::Oo => 'o',
+    };
+    let (expanded, origins) = expand_long_vowels(input, long_o);
+    let syllables = split_syllables(&expanded, is_eng, options);
+    let offsets = syllable_offsets(&expanded, &syllables);
     // After the syllables have been parsed, we can get the kana values for them
-    for c in &syllables {
+    for (i, c) in syllables.iter().enumerate() {
         let temp = c.to_string();
         let mut tempchar = c.chars();
-        if !tempchar.next().unwrap().is_alphabetic() {
+        if let Some(&(_, hiragana, _)) = mode_kana(&temp, options) {
+            output.push_str(hiragana);
+        } else if !tempchar.next().unwrap().is_alphabetic() {
             output.push_str(&temp);
         } else {
-            let result = HIRAGANA.get(&temp);
-            match result {
-                Some(_) => output.push_str(result.unwrap()),
-                None => return Err("Unable to parse input".to_string()),
+            let kana = HIRAGANA.get(&temp)
+                .filter(|_| is_eng || !is_lone_consonant(&temp))
+                .map(String::as_str);
+            match kana {
+                Some(result) => output.push_str(result),
+                None => return Err(unknown_syllable(input, &origins, offsets[i], offsets[i + 1])),
             }
         }
     }
@@ -172,91 +343,190 @@ pub fn to_hiragana(input: &str, is_eng: bool) -> Result<String, String> {
 /// * `input` - A string slice that will be converted to katakana
 /// * `is_eng` - A boolean value that is set if the input origin is English
 ///
+/// # Errors
+///
+/// Returns `KanaError::UnknownSyllable` with the position of the first
+/// syllable that has no katakana.
+///
 ///  # Examples
 ///
 ///  ```
+///  use to_kana::to_katakana;
+///
 ///  let input = "kana";
-///  assert_eq!(to_katakana(input).unwrap(), "カナ");
-///  ````
+///  assert_eq!(to_katakana(input, false).unwrap(), "カナ");
+///  ```
+///
+pub fn to_katakana(input: &str, is_eng: bool) -> Result<String, KanaError> {
+    to_katakana_with(input, is_eng, &KanaOptions::default())
+}
+
+///  Returns a result that gives a string in katakana on success, reading the
+/// input as set out by `options`.
+///
+/// # Errors
+///
+/// Returns `KanaError::UnknownSyllable` with the position of the first
+/// syllable that has no katakana.
 ///
-pub fn to_katakana(input: &str, is_eng: bool) -> Result<String, String> {
+///  # Examples
+///
+///  ```
+///  use to_kana::{to_katakana_with, KanaOptions};
+///
+///  let options = KanaOptions { historical: true, ..KanaOptions::default() };
+///  assert_eq!(to_katakana_with("wiwe", false, &options).unwrap(), "ヰヱ");
+///  ```
+///
+pub fn to_katakana_with(input: &str, is_eng: bool, options: &KanaOptions)
+        -> Result<String, KanaError> {
     let mut output = "".to_string();
 
-    let syllables = to_japanese_syllables(input, is_eng);
+    // Long vowels are doubled so that they are written with the choonpu.
+    let (expanded, origins) = expand_long_vowels(input, 'o');
+    let syllables = split_syllables(&expanded, is_eng, options);
+    let offsets = syllable_offsets(&expanded, &syllables);
 
     let mut last_vowel = ' ';
     // After the syllables have been parsed, we can get the kana values for them
-    for c in &syllables {
+    for (i, c) in syllables.iter().enumerate() {
         let mut temp = c.to_string();
-        if &last_vowel.to_string() == c && last_vowel.is_alphabetic() {
+        if &last_vowel.to_string() == c && "aiueo".contains(last_vowel) {
             // This retrieves the choonpu used for long vowels in katakana.
             temp = "L".to_string();
         }
 
-        if !c.chars().next().unwrap().is_alphabetic() {
+        if let Some(&(_, _, katakana)) = mode_kana(&temp, options) {
+            output.push_str(katakana);
+        } else if !c.chars().next().unwrap().is_alphabetic() {
             output.push_str(&temp);
         } else {
-            let result = KATAKANA.get(&temp);
-            match result {
-                Some(_) => output.push_str(result.unwrap()),
-                None => return Err("Unable to parse input".to_string()),
+            let kana = KATAKANA.get(&temp)
+                .filter(|_| is_eng || !is_lone_consonant(&temp))
+                .map(String::as_str);
+            match kana {
+                Some(result) => output.push_str(result),
+                None => return Err(unknown_syllable(input, &origins, offsets[i], offsets[i + 1])),
             }
         }
         last_vowel = c.chars().last().unwrap();
     }
+    if options.halfwidth {
+        output = katakana_to_halfwidth(&output);
+    }
     Ok(output)
 }
 
+/// Returns the hiragana or katakana for a roomaji syllable as `options`
+/// reads it.
+pub(crate) fn syllable_kana(syllable: &str, options: &KanaOptions, katakana: bool)
+        -> Option<&'static str> {
+    match mode_kana(syllable, options) {
+        Some(&(_, hiragana, _)) if !katakana => Some(hiragana),
+        Some(&(_, _, katakana)) => Some(katakana),
+        None if katakana => KATAKANA.get(syllable).map(String::as_str),
+        None => HIRAGANA.get(syllable).map(String::as_str),
+    }
+}
+
+/// Returns true if some roomaji syllable that `options` reads is longer than
+/// `prefix` and starts with it.
+pub(crate) fn continues_syllable(prefix: &str, options: &KanaOptions) -> bool {
+    let longer = |syllable: &str| syllable.len() > prefix.len() && syllable.starts_with(prefix);
+    HIRAGANA.keys().any(|syllable| longer(syllable))
+        || mode_tables(options).iter().any(|table| table.iter().any(|&(roomaji, _, _)| longer(roomaji)))
+}
+
+/// Returns the kana for a syllable that `options` reads differently from
+/// the tables, as roomaji, hiragana and katakana.
+fn mode_kana(syllable: &str, options: &KanaOptions)
+        -> Option<&'static (&'static str, &'static str, &'static str)> {
+    mode_tables(options)
+        .into_iter()
+        .flat_map(|table| table.iter())
+        .find(|&&(roomaji, _, _)| roomaji == syllable)
+}
+
+/// Returns the tables of syllables that `options` reads differently, in the
+/// order they take precedence.
+fn mode_tables(options: &KanaOptions) -> Vec<&'static [(&'static str, &'static str, &'static str)]> {
+    let mut tables: Vec<&'static [(&'static str, &'static str, &'static str)]> = Vec::new();
+    if options.historical {
+        tables.push(&HISTORICAL_KANA);
+    }
+    if options.wapuro {
+        tables.push(WAPURO_KANA);
+    }
+    match options.system {
+        RomanizationSystem::NihonShiki => tables.extend_from_slice(&[&NIHON_SHIKI_KANA, &KUNREI_KANA]),
+        RomanizationSystem::Kunrei => tables.push(&KUNREI_KANA),
+        _ => {}
+    }
+    tables
+}
+
+/// Builds the error for a syllable that has no kana, quoting the input as it
+/// was written rather than the lowercased syllable. The syllable runs from
+/// char `start` to char `end` of the input as expanded by
+/// `expand_long_vowels`.
+fn unknown_syllable(input: &str, origins: &[usize], start: usize, end: usize) -> KanaError {
+    let position = origins[start];
+    // A syllable that ends halfway through a long vowel quotes all of it.
+    let end = origins[end..]
+        .iter()
+        .cloned()
+        .find(|&origin| origin > position)
+        .unwrap_or(input.len());
+    KanaError::UnknownSyllable { position, fragment: input[position..end].to_string() }
+}
+
 /// Returns a result that gives a string output of Latin 1 characters
 /// from hiragana input on success
 ///
 /// # Arguments
 ///
-/// * `input` - A string slice in hiragana
+/// * `input` - A string slice in hiragana, in which voiced kana may be
+///   written with a combining or spacing voicing mark, as in か゛, and the
+///   choonpu lengthens the vowel before it, as in らーめん
 ///
-pub fn to_roomaji_hiragana(input: &str)-> Result<String, String> {
-    let mut output = "".to_string();
-    
-    let mut last_char = ' ';
-    // This value is for the small tsu used to mark geminates
-    let geminate = '\u{3063}';
-    // These are vowels used in the formation of digraphs
-    let digraph_vowels = ['\u{3083}', 'a' , '\u{3085}', 'u', '\u{3087}', 'o', 
-    '\u{3047}', 'e'];
-    // These kana are romanized atypically from other digraph consonants
-    let digraph_sibilants = ['\u{3057}', '\u{3058}', '\u{3061}'];
-    
-    for c in input.chars(){
-        if !c.is_alphabetic(){
-            output.push(c);
-        } else if digraph_vowels.contains(&c) {
-            output.pop();
-
-            if !digraph_sibilants.contains(&last_char) {
-                output.push('y');
-            }
-            let index = digraph_vowels.into_iter().position(|x| x == &c).unwrap();
-            output.push(digraph_vowels[index + 1]);
-            
-        } else {
-            let mut temp = c.to_string();
-            let result = ROOMAJI_HIRAGANA.get(&temp);
-        
-            if last_char == geminate {
-                output.pop();
-                let first_char = result.unwrap().chars().next().unwrap();
-                output.push(first_char);
-            }
-            match result {
-                Some(_) => output.push_str(result.unwrap()),
-                None => return Err("Unable to parse input".to_string()),
-            }
-            last_char = c;
-        }
-    }
+/// # Errors
+///
+/// Returns `KanaError::UnmappableKana` for characters that are not hiragana
+/// and for voicing marks that do not follow a kana they can voice, and
+/// `KanaError::DanglingSmallKana` for small kana that do not follow a kana
+/// they can combine with.
+///
+pub fn to_roomaji_hiragana(input: &str)-> Result<String, KanaError> {
+    read_hiragana(input).map(|morae| write_roomaji(&morae))
+}
 
-    Ok(output)
-    
+/// Returns a result that gives hiragana input romanized in `system`. The
+/// choonpu is written as a long vowel, as in `to_roomaji_katakana_with`.
+///
+/// # Errors
+///
+/// The same as `to_roomaji_hiragana`.
+///
+/// # Examples
+///
+/// ```
+/// use to_kana::{to_roomaji_hiragana_with, RomanizationSystem};
+///
+/// let input = "しんぶん を よむ";
+/// assert_eq!(to_roomaji_hiragana_with(input, RomanizationSystem::ModifiedHepburn).unwrap(), "shinbun o yomu");
+/// assert_eq!(to_roomaji_hiragana_with(input, RomanizationSystem::TraditionalHepburn).unwrap(), "shimbun wo yomu");
+/// assert_eq!(to_roomaji_hiragana_with(input, RomanizationSystem::Kunrei).unwrap(), "sinbun o yomu");
+/// ```
+///
+pub fn to_roomaji_hiragana_with(input: &str, system: RomanizationSystem)
+        -> Result<String, KanaError> {
+    read_hiragana(input).map(|morae| write_system(&morae, system))
+}
+
+fn read_hiragana(input: &str) -> Result<Vec<Mora<'static>>, KanaError> {
+    // The small tsu used to mark geminates and the choonpu, which hiragana
+    // shares with katakana
+    read_normalized(input, &ROOMAJI_HIRAGANA, '\u{3063}', Some('\u{30FC}'))
 }
 
 /// Returns a result that gives a string output of Latin 1 characters
@@ -264,62 +534,320 @@ pub fn to_roomaji_hiragana(input: &str)-> Result<String, String> {
 ///
 /// # Arguments
 ///
-/// * `input` - A string slice in katakana
+/// * `input` - A string slice in katakana, which may be JIS X 0201
+///   half-width katakana such as ｶﾞｯｺｳ, and in which voiced kana may be
+///   written with a combining or spacing voicing mark
 ///
-pub fn to_roomaji_katakana(input: &str)-> Result<String, String> {
+/// # Errors
+///
+/// Returns `KanaError::UnmappableKana` for characters that are not katakana
+/// and for voicing marks that do not follow a kana they can voice, and
+/// `KanaError::DanglingSmallKana` for small kana that do not follow a kana
+/// they can combine with.
+///
+pub fn to_roomaji_katakana(input: &str)-> Result<String, KanaError> {
+    read_katakana(input).map(|morae| write_roomaji(&morae))
+}
+
+/// Returns a result that gives katakana input romanized in `system`. The
+/// choonpu is written as a long vowel of the mora before it, with a macron,
+/// a circumflex or an h as the system spells it.
+///
+/// # Errors
+///
+/// The same as `to_roomaji_katakana`.
+///
+/// # Examples
+///
+/// ```
+/// use to_kana::{to_roomaji_katakana_with, RomanizationSystem};
+///
+/// let input = "コーヒー";
+/// assert_eq!(to_roomaji_katakana_with(input, RomanizationSystem::ModifiedHepburn).unwrap(), "kōhī");
+/// assert_eq!(to_roomaji_katakana_with(input, RomanizationSystem::NihonShiki).unwrap(), "kôhî");
+/// ```
+///
+pub fn to_roomaji_katakana_with(input: &str, system: RomanizationSystem)
+        -> Result<String, KanaError> {
+    read_katakana(input).map(|morae| write_system(&morae, system))
+}
+
+fn read_katakana(input: &str) -> Result<Vec<Mora<'static>>, KanaError> {
+    // The small tsu used to mark geminates and the choonpu used to mark long
+    // vowels
+    read_normalized(input, &ROOMAJI_KATAKANA, '\u{30C3}', Some('\u{30FC}'))
+}
+
+/// Reads kana as `read_kana` does after writing half-width katakana in full
+/// width, composing voicing marks and expanding iteration marks, with errors
+/// placed back in the input.
+fn read_normalized(
+    input: &str,
+    table: &'static HashMap<String, String>,
+    geminate: char,
+    choonpu: Option<char>,
+) -> Result<Vec<Mora<'static>>, KanaError> {
+    let (normalized, origins) = normalize_kana(input);
+    let relocate = |position: usize, kana: String| {
+        let index = normalized[..position].chars().count();
+        let start = origins[index];
+        let end = origins[index + kana.chars().count()];
+        (start, input[start..end].to_string())
+    };
+    read_kana(&normalized, table, geminate, choonpu).map_err(|error| match error {
+        KanaError::UnmappableKana { position, kana } => {
+            let (position, kana) = relocate(position, kana);
+            KanaError::UnmappableKana { position, kana }
+        }
+        KanaError::DanglingSmallKana { position, kana } => {
+            let (position, kana) = relocate(position, kana);
+            KanaError::DanglingSmallKana { position, kana }
+        }
+        error => error,
+    })
+}
+
+/// A syllable of kana input, as read by `read_kana`.
+enum Mora<'a> {
+    /// Kana and its roomaji from the tables, and whether a small tsu doubles
+    /// its consonant.
+    Syllable { kana: String, roomaji: &'a str, geminate: bool },
+    /// The choonpu, with the vowel of the mora before it that it lengthens,
+    /// or None if that mora has no vowel, as ん does.
+    Long(Option<char>),
+    /// Anything other than kana, which is passed through.
+    Other(char),
+}
+
+/// Reads kana by taking the longest run of kana in `table` at each step, so
+/// that digraphs such as きゃ and extended kana such as ファ are read as one
+/// syllable.
+fn read_kana<'a>(
+    input: &str,
+    table: &'a HashMap<String, String>,
+    geminate: char,
+    choonpu: Option<char>,
+) -> Result<Vec<Mora<'a>>, KanaError> {
+    let mut morae = Vec::new();
+    let mut pending_geminate = None;
+
+    let chars: Vec<(usize, char)> = input.char_indices().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let (position, c) = chars[i];
+        // A voicing mark left over after normalizing had no kana to voice.
+        if is_voicing_mark(c) {
+            return Err(KanaError::UnmappableKana { position, kana: c.to_string() });
+        }
+        if !c.is_alphabetic() {
+            dangling_geminate(pending_geminate, geminate)?;
+            morae.push(Mora::Other(c));
+            i += 1;
+            continue;
+        }
+        if c == geminate {
+            dangling_geminate(pending_geminate, geminate)?;
+            pending_geminate = Some(position);
+            i += 1;
+            continue;
+        }
+        if Some(c) == choonpu {
+            let after_kana = !matches!(morae.last(), Some(&Mora::Other(_)) | None);
+            if !after_kana || pending_geminate.is_some() {
+                return Err(KanaError::UnmappableKana { position, kana: c.to_string() });
+            }
+            let vowel = match morae.last() {
+                Some(&Mora::Syllable { roomaji, .. }) => roomaji.chars().last().filter(|c| "aiueo".contains(*c)),
+                Some(&Mora::Long(vowel)) => vowel,
+                _ => None,
+            };
+            morae.push(Mora::Long(vowel));
+            i += 1;
+            continue;
+        }
+
+        let longest = (1..MAX_KANA_LENGTH + 1)
+            .rev()
+            .filter(|&length| i + length <= chars.len())
+            .filter_map(|length| {
+                let kana: String = chars[i..i + length].iter().map(|&(_, c)| c).collect();
+                table.get(&kana).map(|roomaji| (length, kana, roomaji))
+            })
+            .next();
+        let (length, kana, roomaji) = match longest {
+            Some(found) => found,
+            None if is_small_kana(c) => {
+                return Err(KanaError::DanglingSmallKana { position, kana: c.to_string() })
+            }
+            None => return Err(KanaError::UnmappableKana { position, kana: c.to_string() }),
+        };
+        morae.push(Mora::Syllable {
+            kana,
+            roomaji,
+            geminate: pending_geminate.take().is_some(),
+        });
+        i += length;
+    }
+    dangling_geminate(pending_geminate, geminate)?;
+
+    Ok(morae)
+}
+
+/// Writes out morae in the spelling read back by `to_hiragana`, where long
+/// vowels are doubled and the choonpu repeats the vowel before it. A choonpu
+/// with no vowel before it is written as a hyphen, as it is typed.
+fn write_roomaji(morae: &[Mora]) -> String {
     let mut output = "".to_string();
-    
-    let mut last_char = ' ';
-    // This value is for the small tsu used to mark geminates
-    let geminate = '\u{30C3}';
-    // This value is the long vowel marker used in katakana
-    let choonpu = "\u{30FC}";
-    // These are vowels used in the formation of digraphs
-    let digraph_vowels = ['\u{30E3}', 'a' , '\u{30E5}', 'u', '\u{30E7}', 'o', 
-    '\u{30A7}', 'e'];
-    // These kana are romanized atypically from other digraph consonants
-    let digraph_sibilants = ['\u{30B7}', '\u{30B8}', '\u{30C1}'];
-    
-    for c in input.chars(){
-        if !c.is_alphabetic(){
-            output.push(c);
-        } else if digraph_vowels.contains(&c) {
-            output.pop();
-
-            if !digraph_sibilants.contains(&last_char) {
-                output.push('y');
+    let mut after_n = false;
+    for mora in morae {
+        match *mora {
+            Mora::Other(c) => output.push(c),
+            Mora::Long(Some(vowel)) => output.push(vowel),
+            Mora::Long(None) => output.push('-'),
+            Mora::Syllable { roomaji, geminate, .. } => {
+                // Final n is followed by an apostrophe where it could otherwise
+                // be read as the start of the next syllable, as in hon'ya.
+                if after_n && roomaji.starts_with(|c| "aiueoy".contains(c)) {
+                    output.push('\'');
+                }
+                if geminate {
+                    output.push(roomaji.chars().next().unwrap());
+                }
+                output.push_str(roomaji);
             }
-            let index = digraph_vowels.into_iter().position(|x| x == &c).unwrap();
-            output.push(digraph_vowels[index + 1]);
-            
-        } else if c.to_string() == choonpu {
-            let last_char = output.chars().last().unwrap();
-            output.push(last_char);
-                
+        }
+        after_n = match *mora {
+            Mora::Syllable { roomaji, .. } => roomaji == "n",
+            _ => false,
+        };
+    }
+    output
+}
+
+/// Writes out morae in a romanization system.
+fn write_system(morae: &[Mora], system: RomanizationSystem) -> String {
+    let mut output = "".to_string();
+    // The vowel the output ends in, if a following vowel can lengthen it
+    let mut last_vowel = None;
+    let mut after_n = false;
+    for (i, mora) in morae.iter().enumerate() {
+        let (kana, roomaji, geminate) = match *mora {
+            Mora::Other(c) => {
+                output.push(c);
+                last_vowel = None;
+                after_n = false;
+                continue;
+            }
+            // A choonpu after a vowel that is already long, as in ゲーー,
+            // adds nothing.
+            Mora::Long(Some(vowel)) => {
+                if last_vowel.take() == Some(vowel) {
+                    output.pop();
+                    output.push_str(system.long_vowel(vowel).unwrap());
+                }
+                after_n = false;
+                continue;
+            }
+            Mora::Long(None) => {
+                output.push('-');
+                last_vowel = None;
+                after_n = false;
+                continue;
+            }
+            Mora::Syllable { ref kana, roomaji, geminate } => (kana.as_str(), roomaji, geminate),
+        };
+
+        let spelling = if is_particle(morae, i) && system.spells_particles() {
+            if kana == "\u{306F}" { "wa" } else { "e" }
+        } else if kana == "\u{3092}" || kana == "\u{30F2}"
+            || HISTORICAL_KANA.iter().any(|&(_, hiragana, katakana)| kana == hiragana || kana == katakana)
+        {
+            system.wa_row(roomaji)
+        } else if kana.chars().count() > 1 && kana.ends_with(['\u{308E}', '\u{30EE}']) {
+            // くゎ and ぐゎ keep the kwa and gwa that Nihon-shiki reads back
+            roomaji
         } else {
-            let mut temp = c.to_string();
-            let mut result = ROOMAJI_KATAKANA.get(&temp);
-            if temp == choonpu {
-                result = ROOMAJI_KATAKANA.get(&*last_char.to_string());
-                let last_char = result.unwrap().chars().last().unwrap();
-                output.push(last_char);
-                
-            } else {
-            if last_char == geminate {
+            system.respell(roomaji).unwrap_or(roomaji)
+        };
+
+        // Two vowels that make a long vowel, as in おう, おお, うう and ああ
+        if let (Some(previous), false) = (last_vowel, geminate) {
+            if is_long_vowel(previous, spelling) {
                 output.pop();
-                let first_char = result.unwrap().chars().next().unwrap();
-                output.push(first_char);
+                output.push_str(system.long_vowel(previous).unwrap());
+                last_vowel = None;
+                after_n = false;
+                continue;
             }
-            match result {
-                Some(_) => output.push_str(result.unwrap()),
-                None => return Err("Unable to parse input".to_string()),
+        }
+
+        if after_n {
+            if spelling.starts_with(|c| "aiueoy".contains(c)) {
+                output.push_str(system.n_separator());
+            } else if system.labial_n() && spelling.starts_with(|c| "bmp".contains(c)) {
+                output.pop();
+                output.push('m');
             }
+        }
+        if geminate {
+            // Hepburn doubles ch as tch, as in matcha.
+            if system.is_hepburn() && spelling.starts_with("ch") {
+                output.push('t');
+            } else {
+                output.push(spelling.chars().next().unwrap());
             }
-            last_char = c;
         }
+        output.push_str(spelling);
+        last_vowel = spelling.chars().last().filter(|c| "aiueo".contains(*c));
+        after_n = spelling == "n";
     }
+    output
+}
 
-    Ok(output)
+/// Returns true if a vowel syllable lengthens the vowel before it.
+fn is_long_vowel(previous: char, spelling: &str) -> bool {
+    matches!(
+        (previous, spelling),
+        ('a', "a") | ('u', "u") | ('e', "e") | ('o', "o") | ('o', "u")
+    )
+}
+
+/// Returns true if the syllable at `index` is は or へ standing alone between
+/// words, where it is read as a particle.
+fn is_particle(morae: &[Mora], index: usize) -> bool {
+    let is_particle_kana = match morae[index] {
+        Mora::Syllable { ref kana, geminate: false, .. } => kana == "\u{306F}" || kana == "\u{3078}",
+        _ => false,
+    };
+    let is_boundary = |mora: Option<&Mora>| match mora {
+        Some(&Mora::Other(c)) => !c.is_alphabetic(),
+        None => true,
+        _ => false,
+    };
+    is_particle_kana
+        && index > 0
+        && is_boundary(morae.get(index - 1))
+        && is_boundary(morae.get(index + 1))
+}
+
+/// Returns true for the small kana that only combine with the kana before
+/// them, other than the small tsu.
+fn is_small_kana(kana: char) -> bool {
+    matches!(
+        kana,
+        '\u{3041}' | '\u{3043}' | '\u{3045}' | '\u{3047}' | '\u{3049}'
+        | '\u{3083}' | '\u{3085}' | '\u{3087}'
+        | '\u{30A1}' | '\u{30A3}' | '\u{30A5}' | '\u{30A7}' | '\u{30A9}'
+        | '\u{30E3}' | '\u{30E5}' | '\u{30E7}'
+    )
+}
+
+/// Returns an error if a small tsu is still waiting for a consonant to double.
+fn dangling_geminate(pending: Option<usize>, geminate: char) -> Result<(), KanaError> {
+    match pending {
+        Some(position) => Err(KanaError::DanglingSmallKana { position, kana: geminate.to_string() }),
+        None => Ok(()),
+    }
 }
 
 /// Tests
@@ -478,4 +1006,426 @@ fn test_roomaji_katakana_multiple_words_with_whitespace() {
 #[test]
 fn test_roomaji_katakana_digraphs() {
     assert_eq!("jon myuu", to_roomaji_katakana("ジョン ミュー").unwrap());
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_hiragana_unknown_syllable_position() {
+    assert_eq!(
+        Err(KanaError::UnknownSyllable { position: 4, fragment: "Lu".to_string() }),
+        to_hiragana("kabeLu", false)
+    );
+}
+
+#[test]
+fn test_hiragana_lone_consonants_are_unknown() {
+    assert_eq!(
+        Err(KanaError::UnknownSyllable { position: 0, fragment: "kta".to_string() }),
+        to_hiragana("kta", false)
+    );
+    assert_eq!(
+        Err(KanaError::UnknownSyllable { position: 4, fragment: "kye".to_string() }),
+        to_hiragana("hon kye", false)
+    );
+    assert_eq!(
+        Err(KanaError::UnknownSyllable { position: 2, fragment: "hwa".to_string() }),
+        to_katakana("a hwa", false)
+    );
+    assert_eq!(
+        Err(KanaError::UnknownSyllable { position: 4, fragment: "s".to_string() }),
+        to_hiragana("kabes", false)
+    );
+    assert_eq!("ぐらぶ", to_hiragana("grab", true).unwrap());
+}
+
+#[test]
+fn test_roomaji_hiragana_unmappable_kana_position() {
+    let error = to_roomaji_hiragana("かな漢字").unwrap_err();
+    assert_eq!(Some(6), error.position());
+    assert_eq!(Some("漢"), error.fragment());
+}
+
+#[test]
+fn test_roomaji_hiragana_dangling_small_kana() {
+    assert_eq!(
+        Err(KanaError::DanglingSmallKana { position: 3, kana: "\u{3083}".to_string() }),
+        to_roomaji_hiragana("かゃ")
+    );
+    assert_eq!(
+        Err(KanaError::DanglingSmallKana { position: 3, kana: "\u{3063}".to_string() }),
+        to_roomaji_hiragana("がっ こう")
+    );
+}
+
+#[test]
+fn test_roomaji_katakana_dangling_choonpu() {
+    assert_eq!(
+        Err(KanaError::UnmappableKana { position: 0, kana: "\u{30FC}".to_string() }),
+        to_roomaji_katakana("ーカ")
+    );
+}
+
+#[test]
+fn test_katakana_extended_kana() {
+    assert_eq!(
+        "ファン ティー ディスコ ヴァイオリン ツァー シェフ チェス フュージョン トゥ",
+        to_katakana("fan tii disuko vaiorin tsaa shefu chesu fyuujon twu", false).unwrap()
+    );
+}
+
+#[test]
+fn test_katakana_extended_kana_keeps_dji_and_dzu() {
+    assert_eq!("ヂヅ", to_katakana("djidzu", false).unwrap());
+}
+
+#[test]
+fn test_roomaji_katakana_extended_kana() {
+    assert_eq!(
+        "fan tii disuko vaiorin tsaa shefu chesu fyuujon tu kwo",
+        to_roomaji_katakana("ファン ティー ディスコ ヴァイオリン ツァー シェフ チェス フュージョン トゥ クォ").unwrap()
+    );
+}
+
+#[test]
+fn test_roomaji_hiragana_extended_kana() {
+    assert_eq!("fairu", to_roomaji_hiragana("ふぁいる").unwrap());
+}
+
+#[test]
+fn test_wa_row_round_trip() {
+    for roomaji in &["wa", "wo", "wi", "we", "watashi wo"] {
+        let hiragana = to_hiragana(roomaji, false).unwrap();
+        assert_eq!(*roomaji, to_roomaji_hiragana(&hiragana).unwrap());
+        let katakana = to_katakana(roomaji, false).unwrap();
+        assert_eq!(*roomaji, to_roomaji_katakana(&katakana).unwrap());
+    }
+    assert_eq!("わを", to_hiragana("wawo", false).unwrap());
+    assert_eq!("ウィウェウォ", to_katakana("wiwewho", false).unwrap());
+}
+
+#[test]
+fn test_wa_row_historical_round_trip() {
+    let options = KanaOptions { historical: true, ..KanaOptions::default() };
+    for roomaji in &["wa", "wi", "we", "wo", "wiwewo"] {
+        let hiragana = to_hiragana_with(roomaji, false, &options).unwrap();
+        assert_eq!(*roomaji, to_roomaji_hiragana(&hiragana).unwrap());
+        let katakana = to_katakana_with(roomaji, false, &options).unwrap();
+        assert_eq!(*roomaji, to_roomaji_katakana(&katakana).unwrap());
+    }
+    assert_eq!("ゐゑを", to_hiragana_with("wiwewo", false, &options).unwrap());
+    assert_eq!("ヰヱヲ", to_katakana_with("wiwewo", false, &options).unwrap());
+}
+
+#[test]
+fn test_hiragana_final_n_apostrophe() {
+    assert_eq!("ほんや ほにゃ", to_hiragana("hon'ya honya", false).unwrap());
+    assert_eq!("きんえん", to_hiragana("kin'en", false).unwrap());
+    assert_eq!("ぜんいん", to_hiragana("zenn'in", false).unwrap());
+    assert_eq!("ゼンイン", to_katakana("zenn'in", false).unwrap());
+    let options = KanaOptions { wapuro: true, ..KanaOptions::default() };
+    assert_eq!("ぜんいん", to_hiragana_with("zenn'in", false, &options).unwrap());
+}
+
+#[test]
+fn test_hiragana_double_n() {
+    assert_eq!("こんにちは", to_hiragana("konnichiha", false).unwrap());
+    assert_eq!("こんにちは", to_hiragana("konnnichiha", false).unwrap());
+    assert_eq!("しんぶん", to_hiragana("shinnbunn", false).unwrap());
+}
+
+#[test]
+fn test_katakana_legacy_nq() {
+    assert_eq!("ホニャ", to_katakana("honqa", false).unwrap());
+}
+
+#[test]
+fn test_roomaji_final_n_apostrophe() {
+    assert_eq!("hon'ya honya kin'en shinbun", to_roomaji_hiragana("ほんや ほにゃ きんえん しんぶん").unwrap());
+    assert_eq!("ten'in", to_roomaji_katakana("テンイン").unwrap());
+}
+
+#[test]
+fn test_roomaji_modified_hepburn() {
+    assert_eq!(
+        "watashi wa tōkyō e ikimasu",
+        to_roomaji_hiragana_with("わたし は とうきょう へ いきます", RomanizationSystem::ModifiedHepburn).unwrap()
+    );
+    assert_eq!("hon o yomu", to_roomaji_hiragana_with("ほん を よむ", RomanizationSystem::ModifiedHepburn).unwrap());
+    assert_eq!("kin'en matcha", to_roomaji_hiragana_with("きんえん まっちゃ", RomanizationSystem::ModifiedHepburn).unwrap());
+}
+
+#[test]
+fn test_roomaji_traditional_hepburn() {
+    assert_eq!(
+        "shimbun wo yomu hon-ya",
+        to_roomaji_hiragana_with("しんぶん を よむ ほんや", RomanizationSystem::TraditionalHepburn).unwrap()
+    );
+}
+
+#[test]
+fn test_roomaji_kunrei() {
+    assert_eq!(
+        "tyûsya wa zyôzu zi",
+        to_roomaji_hiragana_with("ちゅうしゃ は じょうず ぢ", RomanizationSystem::Kunrei).unwrap()
+    );
+    assert_eq!("mattya", to_roomaji_hiragana_with("まっちゃ", RomanizationSystem::Kunrei).unwrap());
+}
+
+#[test]
+fn test_roomaji_nihon_shiki() {
+    assert_eq!(
+        "di du dya ha wo",
+        to_roomaji_hiragana_with("ぢ づ ぢゃ は を", RomanizationSystem::NihonShiki).unwrap()
+    );
+}
+
+#[test]
+fn test_roomaji_kunrei_extended_kana_round_trip() {
+    let kunrei = KanaOptions { system: RomanizationSystem::Kunrei, ..KanaOptions::default() };
+    let roomaji = to_roomaji_katakana_with("ティ トゥ テュ チ ツ チュ", RomanizationSystem::Kunrei).unwrap();
+    assert_eq!("thi twu thu ti tu tyu", roomaji);
+    assert_eq!("ティ トゥ テュ チ ツ チュ", to_katakana_with(&roomaji, false, &kunrei).unwrap());
+
+    let nihon_shiki = KanaOptions { system: RomanizationSystem::NihonShiki, ..KanaOptions::default() };
+    let roomaji = to_roomaji_katakana_with("ディ ドゥ デュ ヂ ヅ ヂュ", RomanizationSystem::NihonShiki).unwrap();
+    assert_eq!("dhi dwu dhu di du dyu", roomaji);
+    assert_eq!("ディ ドゥ デュ ヂ ヅ ヂュ", to_katakana_with(&roomaji, false, &nihon_shiki).unwrap());
+
+    let extended = "シェ チェ ジェ ウィ ウェ クァ グァ";
+    let roomaji = to_roomaji_katakana_with(extended, RomanizationSystem::Kunrei).unwrap();
+    assert_eq!("sye tye zye wi we kwa gwa", roomaji);
+    assert_eq!(extended, to_katakana_with(&roomaji, false, &kunrei).unwrap());
+    let roomaji = to_roomaji_katakana_with(extended, RomanizationSystem::NihonShiki).unwrap();
+    assert_eq!("sye tye zye whi whe qwa gwha", roomaji);
+    assert_eq!(extended, to_katakana_with(&roomaji, false, &nihon_shiki).unwrap());
+
+    let native = "ヰ ヱ クヮ グヮ";
+    let roomaji = to_roomaji_katakana_with(native, RomanizationSystem::NihonShiki).unwrap();
+    assert_eq!("wi we kwa gwa", roomaji);
+    assert_eq!(native, to_katakana_with(&roomaji, false, &nihon_shiki).unwrap());
+    assert_eq!("ウィンドー", to_katakana_with(
+        &to_roomaji_katakana_with("ウィンドウ", RomanizationSystem::NihonShiki).unwrap(), false, &nihon_shiki).unwrap());
+}
+
+#[test]
+fn test_roomaji_passport() {
+    assert_eq!(
+        "ohno shumpei",
+        to_roomaji_hiragana_with("おおの しゅんぺい", RomanizationSystem::Passport).unwrap()
+    );
+    assert_eq!("ramen", to_roomaji_katakana_with("ラーメン", RomanizationSystem::Passport).unwrap());
+}
+
+#[test]
+fn test_roomaji_katakana_systems_lengthen_choonpu() {
+    assert_eq!("kōhī", to_roomaji_katakana_with("コーヒー", RomanizationSystem::ModifiedHepburn).unwrap());
+    assert_eq!("kôhî", to_roomaji_katakana_with("コーヒー", RomanizationSystem::Kunrei).unwrap());
+}
+
+#[test]
+fn test_hiragana_kunrei_input() {
+    let options = KanaOptions { system: RomanizationSystem::Kunrei, ..KanaOptions::default() };
+    assert_eq!(
+        to_hiragana("shashin chizu tsukue chuusha jouzu fuji", false).unwrap(),
+        to_hiragana_with("syasin tizu tukue tyuusya zyouzu huzi", false, &options).unwrap()
+    );
+}
+
+#[test]
+fn test_kunrei_extended_e_digraphs() {
+    assert_eq!("しぇ じぇ ちぇ", to_hiragana("sye zye tye", false).unwrap());
+    let options = KanaOptions { system: RomanizationSystem::Kunrei, ..KanaOptions::default() };
+    assert_eq!("シェフ ジェット チェス", to_katakana_with("syehu zyetto tyesu", false, &options).unwrap());
+}
+
+#[test]
+fn test_hiragana_nihon_shiki_input() {
+    let options = KanaOptions { system: RomanizationSystem::NihonShiki, ..KanaOptions::default() };
+    assert_eq!(
+        "はなぢ つづく ぢゃ ぢゅ ぢょ くゎ ぐゎ ゐ ゑ を",
+        to_hiragana_with("hanadi tuduku dya dyu dyo kwa gwa wi we wo", false, &options).unwrap()
+    );
+}
+
+#[test]
+fn test_nihon_shiki_small_wa_round_trip() {
+    let options = KanaOptions { system: RomanizationSystem::NihonShiki, ..KanaOptions::default() };
+    let hiragana = to_hiragana_with("kwasi gwaikoku", false, &options).unwrap();
+    assert_eq!("くゎし ぐゎいこく", hiragana);
+    assert_eq!("kwasi gwaikoku", to_roomaji_hiragana_with(&hiragana, RomanizationSystem::NihonShiki).unwrap());
+    let katakana = to_katakana_with("kwasi gwaikoku", false, &options).unwrap();
+    assert_eq!("クヮシ グヮイコク", katakana);
+    assert_eq!("kwasi gwaikoku", to_roomaji_katakana_with(&katakana, RomanizationSystem::NihonShiki).unwrap());
+}
+
+#[test]
+fn test_hiragana_unambiguous_kunrei_in_hepburn() {
+    assert_eq!("しゃしん じょうず ちょっと", to_hiragana("syasin zyouzu tyotto", false).unwrap());
+    assert_eq!("ティ トゥ テュ", to_katakana("ti tu tyu", false).unwrap());
+}
+
+#[test]
+fn test_hiragana_macron_long_vowels() {
+    assert_eq!("とうきょう おばあさん", to_hiragana("Tōkyō obāsan", false).unwrap());
+    assert_eq!("きょうと すうがく", to_hiragana("Kyôto sûgaku", false).unwrap());
+    assert_eq!("とうきょう", to_hiragana("TO\u{304}KYO\u{302}", false).unwrap());
+}
+
+#[test]
+fn test_hiragana_long_o_as_oo() {
+    let options = KanaOptions { long_o: LongO::Oo, ..KanaOptions::default() };
+    assert_eq!("おおさか", to_hiragana_with("Ōsaka", false, &options).unwrap());
+}
+
+#[test]
+fn test_katakana_macron_long_vowels() {
+    assert_eq!("コーヒー ラーメン", to_katakana("kōhī rāmen", false).unwrap());
+    assert_eq!("トーキョー", to_katakana("To\u{302}kyo\u{304}", false).unwrap());
+}
+
+#[test]
+fn test_hiragana_unknown_syllable_position_after_macron() {
+    assert_eq!(
+        Err(KanaError::UnknownSyllable { position: 3, fragment: "Lu".to_string() }),
+        to_hiragana("tōLu", false)
+    );
+}
+
+#[test]
+fn test_hiragana_hepburn_tch() {
+    assert_eq!("まっちゃ こっち", to_hiragana("matcha kotchi", false).unwrap());
+    assert_eq!("まっちゃ", to_hiragana("maccha", false).unwrap());
+}
+
+#[test]
+fn test_katakana_hepburn_m_before_labials() {
+    assert_eq!("シンブン ナンバ センパイ センモン", to_katakana("shimbun Namba sempai semmon", false).unwrap());
+    assert_eq!("ムラ", to_katakana("mura", false).unwrap());
+}
+
+#[test]
+fn test_hiragana_wapuro_input() {
+    let options = KanaOptions { wapuro: true, ..KanaOptions::default() };
+    assert_eq!("きって", to_hiragana_with("kixtute", false, &options).unwrap());
+    assert_eq!("きゃ ふぁ ゃ っ", to_hiragana_with("kixya fula lya ltsu", false, &options).unwrap());
+    assert_eq!("こんにちは", to_hiragana_with("konnnitiha", false, &options).unwrap());
+    assert_eq!("らーめん", to_hiragana_with("ra-menn", false, &options).unwrap());
+    assert_eq!("ゐ ゑ くぁ でぃ てぃ", to_hiragana_with("wyi wye kwa dhi thi", false, &options).unwrap());
+    assert_eq!("っま", to_hiragana_with("mma", false, &options).unwrap());
+}
+
+#[test]
+fn test_katakana_wapuro_input() {
+    let options = KanaOptions { wapuro: true, ..KanaOptions::default() };
+    assert_eq!("パーティー ディスク", to_katakana_with("pa-thi- dhisuku", false, &options).unwrap());
+    assert_eq!("ヶ", to_katakana_with("xke", false, &options).unwrap());
+}
+
+#[test]
+fn test_wapuro_key_sequences() {
+    let options = KanaOptions { wapuro: true, ..KanaOptions::default() };
+    let sequences = [
+        ("sye", "しぇ", "シェ"),
+        ("jye", "じぇ", "ジェ"),
+        ("zye", "じぇ", "ジェ"),
+        ("tye", "ちぇ", "チェ"),
+        ("cye", "ちぇ", "チェ"),
+        ("dye", "ぢぇ", "ヂェ"),
+        ("kye", "きぇ", "キェ"),
+        ("nye", "にぇ", "ニェ"),
+        ("yi", "い", "イ"),
+        ("xyi", "ぃ", "ィ"),
+        ("lyi", "ぃ", "ィ"),
+        ("qwa", "くぁ", "クァ"),
+        ("whu", "う", "ウ"),
+    ];
+    for &(keys, hiragana, katakana) in &sequences {
+        assert_eq!(hiragana, to_hiragana_with(keys, false, &options).unwrap(), "{}", keys);
+        assert_eq!(katakana, to_katakana_with(keys, false, &options).unwrap(), "{}", keys);
+    }
+}
+
+#[test]
+fn test_roomaji_small_kana_on_their_own() {
+    let options = KanaOptions { wapuro: true, ..KanaOptions::default() };
+    let hiragana = to_hiragana_with("xka xke xwa", false, &options).unwrap();
+    assert_eq!("\u{3095} \u{3096} \u{308E}", hiragana);
+    assert_eq!("ka ke wa", to_roomaji_hiragana(&hiragana).unwrap());
+    let katakana = to_katakana_with("lka lke lwa", false, &options).unwrap();
+    assert_eq!("\u{30F5} \u{30F6} \u{30EE}", katakana);
+    assert_eq!("ka ke wa", to_roomaji_katakana_with(&katakana, RomanizationSystem::ModifiedHepburn).unwrap());
+    assert_eq!(Err(KanaError::DanglingSmallKana { position: 0, kana: "\u{3041}".to_string() }),
+        to_roomaji_hiragana("\u{3041}"));
+}
+
+#[test]
+fn test_roomaji_katakana_halfwidth() {
+    assert_eq!("gakkou paatii", to_roomaji_katakana("ｶﾞｯｺｳ ﾊﾟｰﾃｨｰ").unwrap());
+    assert_eq!(Err(KanaError::DanglingSmallKana { position: 6, kana: "ｯ".to_string() }),
+        to_roomaji_katakana("ｶﾞｯ"));
+    assert_eq!(Err(KanaError::UnmappableKana { position: 9, kana: "ぬ".to_string() }),
+        to_roomaji_katakana("ｶﾞｷぬ"));
+}
+
+#[test]
+fn test_katakana_halfwidth_output() {
+    let options = KanaOptions { halfwidth: true, ..KanaOptions::default() };
+    assert_eq!("ｶﾞｯｺｳ ﾗｰﾒﾝ", to_katakana_with("gakkou raamen", false, &options).unwrap());
+    assert_eq!("がっこう", to_hiragana_with("gakkou", false, &options).unwrap());
+}
+
+#[test]
+fn test_roomaji_hiragana_voicing_marks() {
+    assert_eq!("gakkou", to_roomaji_hiragana("\u{304B}\u{3099}\u{3063}\u{3053}\u{3046}").unwrap());
+    assert_eq!("panda", to_roomaji_katakana("\u{30CF}\u{309C}\u{30F3}\u{30BF}\u{309B}").unwrap());
+    assert_eq!(Err(KanaError::DanglingSmallKana { position: 6, kana: "\u{3083}".to_string() }),
+        to_roomaji_hiragana("\u{304B}\u{3099}\u{3083}"));
+}
+
+#[test]
+fn test_roomaji_uncomposed_voicing_marks() {
+    assert_eq!(Err(KanaError::UnmappableKana { position: 3, kana: "\u{309A}".to_string() }),
+        to_roomaji_hiragana("\u{304B}\u{309A}"));
+    assert_eq!(Err(KanaError::UnmappableKana { position: 3, kana: "\u{FF9F}".to_string() }),
+        to_roomaji_katakana("\u{FF76}\u{FF9F}"));
+    assert_eq!(Err(KanaError::UnmappableKana { position: 6, kana: "\u{309B}".to_string() }),
+        to_roomaji_hiragana("\u{304B}\u{309B}\u{309B}"));
+    assert_eq!(Err(KanaError::UnmappableKana { position: 0, kana: "\u{3099}".to_string() }),
+        to_roomaji_katakana_with("\u{3099}\u{30AB}", RomanizationSystem::Kunrei));
+}
+
+#[test]
+fn test_roomaji_iteration_marks() {
+    assert_eq!("isuzu kokoro", to_roomaji_hiragana("いすゞ こゝろ").unwrap());
+    assert_eq!("sasaki", to_roomaji_katakana("サヽキ").unwrap());
+    assert_eq!(Err(KanaError::UnmappableKana { position: 0, kana: "ゝ".to_string() }),
+        to_roomaji_hiragana("ゝか"));
+}
+
+#[test]
+fn test_roomaji_hiragana_choonpu() {
+    assert_eq!("raamen", to_roomaji_hiragana("らーめん").unwrap());
+    assert_eq!("r\u{101}men", to_roomaji_hiragana_with("らーめん", RomanizationSystem::ModifiedHepburn).unwrap());
+    assert_eq!("t\u{F4}ky\u{F4}", to_roomaji_hiragana_with("とーきょお", RomanizationSystem::Kunrei).unwrap());
+}
+
+#[test]
+fn test_roomaji_choonpu_after_digraphs_and_n() {
+    assert_eq!("kyaa faa tii n-", to_roomaji_katakana("キャー ファー ティー ンー").unwrap());
+    assert_eq!("ky\u{101} f\u{101} t\u{12B} n-",
+        to_roomaji_katakana_with("キャー ファー ティー ンー", RomanizationSystem::ModifiedHepburn).unwrap());
+    assert_eq!("tohkyoh", to_roomaji_katakana_with("トーキョー", RomanizationSystem::Passport).unwrap());
+}
+
+#[test]
+fn test_roomaji_repeated_choonpu() {
+    assert_eq!("geee", to_roomaji_katakana("ゲーー").unwrap());
+    assert_eq!("g\u{113}", to_roomaji_katakana_with("ゲーー", RomanizationSystem::ModifiedHepburn).unwrap());
+    assert_eq!("k\u{14D}", to_roomaji_hiragana_with("こうー", RomanizationSystem::ModifiedHepburn).unwrap());
+}
+
+#[test]
+fn test_unknown_syllable_after_multi_char_lowercase() {
+    let error = KanaError::UnknownSyllable { position: 12, fragment: "qqqq".to_string() };
+    assert_eq!(Err(error.clone()), to_hiragana("\u{130}\u{130}\u{130}\u{130}\u{130}\u{130}qqqq", false));
+    assert_eq!(Err(error), to_katakana("\u{130}\u{130}\u{130}\u{130}\u{130}\u{130}qqqq", false));
+}
@@ -0,0 +1,64 @@
+// Copyright 2018 Jason Brewer and Gavin Megson
+//
+//!  Kana Transliterator
+//!
+//!  Takes a string input in the Latin 1 character set and converts
+//! it to a Japanese hiragana or katakana output, and takes kana and
+//! converts it back to roomaji. English words can be transliterated
+//! through the Carnegie Mellon University phonetic dictionary.
+//!
+//!  # Examples
+//!
+//!  ````
+//!  use to_kana::{to_hiragana, to_katakana, to_roomaji_hiragana};
+//!
+//!  let input = "kana";
+//!  assert_eq!(to_hiragana(input, false).unwrap(), "かな");
+//!  assert_eq!(to_katakana(input, false).unwrap(), "カナ");
+//!  assert_eq!(to_roomaji_hiragana("かな").unwrap(), "kana");
+//!  ````
+//!
+#[macro_use]
+extern crate lazy_static; // 1.0.2
+
+use std::collections::HashMap;
+
+pub mod kana;
+
+pub use kana::cmu::{
+    cmu_hiragana, cmu_hiragana_variants, cmu_katakana, cmu_katakana_variants, CmuDictionary,
+};
+pub use kana::compose::Composer;
+pub use kana::error::KanaError;
+pub use kana::conv::{
+    initialize_hiragana, initialize_hiragana_keys, initialize_japanese_syllables,
+    initialize_katakana, initialize_katakana_keys, initialize_roomaji,
+};
+pub use kana::options::{KanaOptions, LongO, RomanizationSystem};
+pub use kana::script::{
+    add_dakuten, add_handakuten, collapse_iteration_marks, compose_voicing, decompose_voicing,
+    expand_iteration_marks, halfwidth_to_katakana, hiragana_to_katakana, katakana_to_halfwidth,
+    katakana_to_hiragana, remove_voicing,
+};
+pub use kana::trans::{
+    to_hiragana, to_hiragana_with, to_japanese_syllables, to_katakana, to_katakana_with,
+    to_roomaji_hiragana, to_roomaji_hiragana_with, to_roomaji_katakana,
+    to_roomaji_katakana_with,
+};
+
+// Global constant hashmaps that map Latin 1 syllables to Japanese
+// kana in unicode, and kana back to Latin 1 syllables.
+lazy_static! {
+    /// Roomaji syllables to hiragana.
+    pub static ref HIRAGANA: HashMap<String, String> = initialize_hiragana();
+    /// Roomaji syllables to katakana.
+    pub static ref KATAKANA: HashMap<String, String> = initialize_katakana();
+    /// Hiragana to roomaji syllables.
+    pub static ref ROOMAJI_HIRAGANA: HashMap<String, String> = initialize_roomaji(initialize_hiragana_keys);
+    /// Katakana to roomaji syllables.
+    pub static ref ROOMAJI_KATAKANA: HashMap<String, String> = initialize_roomaji(initialize_katakana_keys);
+    /// Hiragana in the same order as `initialize_japanese_syllables`.
+    pub static ref HIRAGANA_KEYS: Vec<&'static str> = initialize_hiragana_keys();
+    /// Katakana in the same order as `initialize_japanese_syllables`.
+    pub static ref KATAKANA_KEYS: Vec<&'static str> = initialize_katakana_keys();
+}
@@ -2,44 +2,119 @@
 //
 //!  Kana Transliterator
 //!
-//!  Takes a string input in the Latin 1 character set and converts
-//! it to a Japanese hiragana or katakana output.
+//!  Command line front end for the `to_kana` library. See the README
+//! for the available options.
 //!
-//!  # Examples
-//!
-//!  ````
-//!  let input = "kana";
-//!  assert_eq!(to_hiragana(input).unwrap(), "かな");
-//!  assert_eq!(to_katakana(input).unwrap(), "カナ");
-//!  ````
-//!
-#[macro_use]
-extern crate lazy_static; // 1.0.2
+extern crate to_kana;
 
 use std::env;
 use std::process;
-use std::collections::HashMap;
-
-mod kana;
-use kana::trans::{to_hiragana, to_katakana, to_roomaji_hiragana, to_roomaji_katakana};
-use kana::cmu::{cmu_hiragana, cmu_katakana};
-use kana::conv::{initialize_hiragana, initialize_hiragana_keys, initialize_katakana,
-    initialize_katakana_keys, initilize_roomaji};
-
-/// Global constant hashmaps that map Latin 1 syllables to Japanese
-/// kana in unicode.
-lazy_static! {
-    pub static ref HIRAGANA: HashMap<String, String> = { initialize_hiragana() };
-    pub static ref KATAKANA: HashMap<String, String> = { initialize_katakana() };
-    pub static ref ROOMAJI_HIRAGANA: HashMap<String, String> = { initilize_roomaji(initialize_hiragana_keys) };
-    pub static ref ROOMAJI_KATAKANA: HashMap<String, String> = { initilize_roomaji(initialize_katakana_keys) };
-    pub static ref HIRAGANA_KEYS: Vec<&'static str> = { initialize_hiragana_keys() };
-    pub static ref KATAKANA_KEYS: Vec<&'static str> = { initialize_katakana_keys() };
+
+use to_kana::{to_hiragana_with, to_katakana_with, to_roomaji_hiragana, to_roomaji_hiragana_with,
+    to_roomaji_katakana, to_roomaji_katakana_with, katakana_to_halfwidth, CmuDictionary, KanaError, KanaOptions, LongO,
+    RomanizationSystem};
+
+
+/// Which CMU pronunciations to transliterate, chosen with `--variant`.
+enum Variant {
+    First,
+    Index(usize),
+    All,
+}
+
+/// Transliterates an English word with the CMU dictionary from `--cmu-data`
+/// if it was given, or the shared dictionary otherwise. All variants are
+/// returned one per line.
+fn cmu_convert(cmu_data: &Option<String>, variant: &Variant, word: &str, katakana: bool)
+        -> Result<String, KanaError> {
+    let loaded;
+    let dictionary = match *cmu_data {
+        Some(ref dir) => {
+            loaded = CmuDictionary::from_dir(dir)?;
+            &loaded
+        }
+        None => CmuDictionary::shared()?,
+    };
+    match (variant, katakana) {
+        (&Variant::First, false) => dictionary.hiragana(word),
+        (&Variant::First, true) => dictionary.katakana(word),
+        (&Variant::Index(index), false) => dictionary.hiragana_variant(word, index),
+        (&Variant::Index(index), true) => dictionary.katakana_variant(word, index),
+        (&Variant::All, false) => dictionary.hiragana_variants(word).map(|all| all.join("\n")),
+        (&Variant::All, true) => dictionary.katakana_variants(word).map(|all| all.join("\n")),
+    }
 }
 
+/// Parses the name given to `--system`.
+fn parse_system(name: &str) -> Option<RomanizationSystem> {
+    match name {
+        "hepburn" | "modified-hepburn" => Some(RomanizationSystem::ModifiedHepburn),
+        "traditional-hepburn" => Some(RomanizationSystem::TraditionalHepburn),
+        "kunrei" => Some(RomanizationSystem::Kunrei),
+        "nihon-shiki" => Some(RomanizationSystem::NihonShiki),
+        "passport" => Some(RomanizationSystem::Passport),
+        _ => None,
+    }
+}
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = Vec::new();
+    let mut cmu_data = None;
+    let mut variant = Variant::First;
+    let mut options = KanaOptions::default();
+    let mut system = None;
+
+    let mut env_args = env::args();
+    while let Some(arg) = env_args.next() {
+        if arg == "--cmu-data" {
+            cmu_data = env_args.next();
+            if cmu_data.is_none() {
+                println!("--cmu-data expects a directory, please see README for details");
+                process::exit(1);
+            }
+        } else if arg == "--system" {
+            system = env_args.next().as_ref().and_then(|name| parse_system(name));
+            match system {
+                Some(system) => options.system = system,
+                None => {
+                    println!("--system expects a romanization system, please see README for details");
+                    process::exit(1);
+                }
+            }
+        } else if arg == "--long-o" {
+            options.long_o = match env_args.next().as_deref() {
+                Some("ou") => LongO::Ou,
+                Some("oo") => LongO::Oo,
+                _ => {
+                    println!("--long-o expects \"ou\" or \"oo\", please see README for details");
+                    process::exit(1);
+                }
+            };
+        } else if arg == "--wapuro" {
+            options.wapuro = true;
+        } else if arg == "--historical" {
+            options.historical = true;
+        } else if arg == "--halfwidth" {
+            options.halfwidth = true;
+        } else if arg == "--variant" {
+            variant = match env_args.next() {
+                Some(ref all) if all == "all" => Variant::All,
+                Some(index) => match index.parse() {
+                    Ok(index) => Variant::Index(index),
+                    Err(_) => {
+                        println!("--variant expects a number or \"all\", please see README for details");
+                        process::exit(1);
+                    }
+                },
+                None => {
+                    println!("--variant expects a number or \"all\", please see README for details");
+                    process::exit(1);
+                }
+            };
+        } else {
+            args.push(arg);
+        }
+    }
 
     if args.len() != 3 {
         println!("Incorrect number of command line arguemnts, please type README in command line for details");
@@ -47,13 +122,32 @@ fn main() {
     }
 
     let option = &args[1];
-    match option.as_str() {
-        "hiragana" => println!("{}", to_hiragana(&args[2], false).expect("Unable to parse input")),
-        "katakana" => println!("{}", to_katakana(&args[2], false).expect("Unable to parse input")),
-        "roomaji_hiragana" => println!("{}", to_roomaji_hiragana(&args[2]).expect("Unable to parse input")),
-        "roomaji_katakana" => println!("{}", to_roomaji_katakana(&args[2]).expect("Unable to parse input")),
-        "cmu_hiragana"   => println!("{}",cmu_hiragana(&args[2])),
-        "cmu_katakana"   => println!("{}",cmu_katakana(&args[2])),
-        _ => println!("Incorrect command line argument, please see README for details."),
+    let result = match option.as_str() {
+        "hiragana" => to_hiragana_with(&args[2], false, &options),
+        "katakana" => to_katakana_with(&args[2], false, &options),
+        "roomaji_hiragana" => match system {
+            Some(system) => to_roomaji_hiragana_with(&args[2], system),
+            None => to_roomaji_hiragana(&args[2]),
+        },
+        "roomaji_katakana" => match system {
+            Some(system) => to_roomaji_katakana_with(&args[2], system),
+            None => to_roomaji_katakana(&args[2]),
+        },
+        "cmu_hiragana"   => cmu_convert(&cmu_data, &variant, &args[2], false),
+        "cmu_katakana"   => cmu_convert(&cmu_data, &variant, &args[2], true).map(|output| {
+            if options.halfwidth { katakana_to_halfwidth(&output) } else { output }
+        }),
+        _ => {
+            println!("Incorrect command line argument, please see README for details.");
+            process::exit(1);
+        }
+    };
+
+    match result {
+        Ok(output) => println!("{}", output),
+        Err(error) => {
+            eprintln!("Unable to parse input: {}", error);
+            process::exit(1);
+        }
     }
-}
\ No newline at end of file
+}